@@ -0,0 +1,48 @@
+use std::{
+    fs::{self, DirBuilder, File},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::config;
+
+pub fn path(name: &str) -> Result<PathBuf> {
+    let mut path = config::dir_path()?;
+    path.push("cache");
+    path.push(format!("{}.json", name));
+    Ok(path)
+}
+
+pub fn load<T: DeserializeOwned>(name: &str) -> Result<T> {
+    let path = path(name)?;
+    let file = File::open(&path).with_context(|| format!("cannot open {}", path.display()))?;
+    let value = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("cannot deserialize {}", path.display()))?;
+    Ok(value)
+}
+
+/// Like `load`, but fails if the cache was stored more than `max_age` ago.
+pub fn load_fresh<T: DeserializeOwned>(name: &str, max_age: Duration) -> Result<T> {
+    let modified = fs::metadata(path(name)?)?.modified()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    if age > max_age {
+        bail!("cache {} is expired", name);
+    }
+    load(name)
+}
+
+pub fn store<T: Serialize>(name: &str, value: &T) -> Result<()> {
+    let path = path(name)?;
+    if let Some(parent) = path.parent() {
+        DirBuilder::new().recursive(true).create(parent)?;
+    }
+    let file = File::create(&path).with_context(|| format!("cannot create {}", path.display()))?;
+    serde_json::to_writer(BufWriter::new(file), value).with_context(|| "serialize error")?;
+    Ok(())
+}
@@ -1,4 +1,22 @@
 mod channel;
 pub mod clap_app;
+mod clip;
+pub mod complete;
+pub mod config;
+mod export;
+mod file;
+mod group;
 pub mod handle;
+pub mod link;
+mod me;
+mod msg;
 pub mod notify;
+pub mod output;
+pub mod picker;
+mod pin;
+pub mod prompt;
+mod search;
+mod stamp;
+mod star;
+mod unread;
+mod user;
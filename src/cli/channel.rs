@@ -1,19 +1,33 @@
 #![allow(dead_code)]
 
+mod find;
+mod manage;
+mod tree;
+
 use std::{
     cell::{Ref, RefCell},
     collections::BTreeMap,
     ffi::OsStr,
-    path::Path,
+    fs::{self, DirBuilder},
+    path::{Path, PathBuf},
     rc::{Rc, Weak},
 };
 
 use anyhow::{bail, Context, Result};
 use clap::ArgMatches;
 use rust_traq::{
-    apis::{self, configuration::Configuration},
-    models::{self, ChannelList},
+    apis::{self, activity_api, configuration::Configuration},
+    models::{self, ChannelList, ChannelSubscribeLevel},
+};
+use serde::Serialize;
+
+use super::{
+    notify,
+    output::{self, OutputFormat, Render},
 };
+use crate::{cache, config};
+pub use tree::SortKey;
+use tree::TreeOptions;
 
 pub struct ChannelTree {
     root: Rc<RefCell<ChannelTreeNode>>,
@@ -31,6 +45,8 @@ impl ChannelTree {
         for p in channel_name {
             if p == OsStr::new("/") {
                 self.go_root();
+            } else if p == OsStr::new(".") {
+                continue;
             } else if p == OsStr::new("..") {
                 if let Err(e) = self.go_up() {
                     self.current = cur;
@@ -60,6 +76,39 @@ impl ChannelTree {
         Ok(id)
     }
 
+    /// Full paths of every channel by id.
+    pub fn paths(&self) -> BTreeMap<ChannelId, String> {
+        self.root()
+            .list_r(true)
+            .into_iter()
+            .map(|e| (e.id, e.path))
+            .collect()
+    }
+
+    /// Entries of the channel at `channel_name` and, if `recursive`, of its descendants.
+    pub fn entries(&mut self, channel_name: &Path, recursive: bool) -> Result<Vec<ChannelEntry>> {
+        let node = self.get(channel_name)?;
+        let node = RefCell::borrow(&node);
+
+        let mut entries = Vec::new();
+        if !node.is_root() {
+            let mut entry = node.entry("", 0);
+            entry.display = entry.path.clone();
+            entries.push(entry);
+        }
+        if recursive {
+            entries.extend(node.list_r(true));
+        }
+        Ok(entries)
+    }
+
+    /// Returns the node at `channel_name` without changing the current channel.
+    pub fn get(&mut self, channel_name: &Path) -> Result<Rc<RefCell<ChannelTreeNode>>> {
+        let cur = Rc::clone(&self.current);
+        self.go_path(channel_name)?;
+        Ok(std::mem::replace(&mut self.current, cur))
+    }
+
     // pub fn as_named_map(self) -> BTreeMap<String, ChannelLike> {}
 
     fn go_root(&mut self) {
@@ -138,18 +187,19 @@ impl ChannelTreeNode {
         }
     }
 
-    fn list(&self, full: bool) {
-        let full = if full {
+    fn list(&self, full: bool) -> Vec<ChannelEntry> {
+        let parent = if full {
             self.get_parent_full_path()
         } else {
             "".to_owned()
         };
         self.children
             .iter()
-            .for_each(|ch| println!("{}/{}", full, &RefCell::borrow(&ch).name));
+            .map(|ch| RefCell::borrow(ch).entry(&parent, 0))
+            .collect()
     }
 
-    fn list_r(&self, full: bool) {
+    fn list_r(&self, full: bool) -> Vec<ChannelEntry> {
         let parent = if full {
             self.get_parent_full_path()
         } else if self.is_root() {
@@ -158,17 +208,33 @@ impl ChannelTreeNode {
             ".".to_owned()
         };
 
-        self._list_r(&parent);
+        let mut entries = Vec::new();
+        self._list_r(&parent, 0, &mut entries);
+        entries
     }
 
-    fn _list_r(&self, parent: &str) {
+    fn _list_r(&self, parent: &str, depth: usize, entries: &mut Vec<ChannelEntry>) {
         self.children.iter().for_each(|ch| {
-            let cur = format!("{}/{}", parent, &RefCell::borrow(&ch).name);
-            println!("{}", cur);
-            RefCell::borrow(ch)._list_r(&cur);
+            let ch = RefCell::borrow(ch);
+            let entry = ch.entry(parent, depth);
+            let cur = entry.display.clone();
+            entries.push(entry);
+            ch._list_r(&cur, depth + 1, entries);
         });
     }
 
+    fn entry(&self, parent: &str, depth: usize) -> ChannelEntry {
+        ChannelEntry {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            path: self.get_parent_full_path(),
+            archived: self.archived,
+            subscription: None,
+            display: format!("{}/{}", parent, self.name),
+            depth,
+        }
+    }
+
     fn is_root(&self) -> bool {
         self.parent.upgrade().is_none()
     }
@@ -191,6 +257,58 @@ impl ChannelTreeNode {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct ChannelEntry {
+    pub id: ChannelId,
+    pub name: String,
+    pub path: String,
+    pub archived: bool,
+    pub subscription: Option<&'static str>,
+    #[serde(skip)]
+    display: String,
+    #[serde(skip)]
+    depth: usize,
+}
+
+impl ChannelEntry {
+    pub fn new(id: ChannelId, path: String) -> Self {
+        let name = path.rsplit('/').next().unwrap_or_default().to_owned();
+        Self {
+            id,
+            name,
+            display: path.clone(),
+            path,
+            archived: false,
+            subscription: None,
+            depth: 0,
+        }
+    }
+}
+
+impl Render for ChannelEntry {
+    fn header() -> &'static [&'static str] {
+        &["id", "name", "path", "archived", "subscription"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.path.clone(),
+            self.archived.to_string(),
+            self.subscription.unwrap_or("").to_owned(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        self.display.clone()
+    }
+
+    fn tree(&self) -> (usize, String) {
+        (self.depth, self.name.clone())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ChannelLike {
     pub id: ChannelId,
@@ -264,39 +382,102 @@ fn construct_tree(
 pub async fn channel(conf: &Configuration, matches: &ArgMatches<'_>, cmd: &str) -> Result<()> {
     match cmd {
         "list" => {
+            let format = OutputFormat::from_matches(matches)?;
             let mut tree = get_channel_tree(conf).await?;
             let cur = Rc::clone(&tree.current);
 
-            if let Some(channel_name) = matches.value_of("channel_name") {
-                let channel_path = Path::new(channel_name);
-                tree.go_path(channel_path)?;
+            match matches.value_of("channel_name") {
+                Some(channel_name) => tree.go_path(Path::new(channel_name))?,
+                None => enter_current_channel(&mut tree),
             }
 
             let full = matches.is_present("full");
-            if matches.is_present("recursive") {
-                tree.cur().list_r(full);
-            } else {
-                tree.cur().list(full);
+            let as_tree =
+                format == OutputFormat::Tree || (format.is_text() && matches.is_present("tree"));
+            if !as_tree
+                && ["depth", "sort", "color"]
+                    .iter()
+                    .any(|a| matches.is_present(a))
+            {
+                bail!("--depth, --sort and --color need --tree or --output tree");
             }
+            if as_tree {
+                let mut opts =
+                    TreeOptions::new(matches.value_of("sort").unwrap_or("name").parse()?);
+                if let Some(depth) = matches.value_of("depth") {
+                    opts.depth = Some(depth.parse().with_context(|| "depth must be a number")?);
+                }
+                opts.color = matches.is_present("color");
+                if opts.color {
+                    opts.levels = notify::get_subscriptions(conf).await?;
+                }
+                if opts.sort == SortKey::Activity {
+                    opts.activity = get_activity_ranks(conf).await?;
+                }
+
+                let lines = {
+                    let node = tree.cur();
+                    let label = if node.is_root() {
+                        "/".to_owned()
+                    } else if full {
+                        node.get_parent_full_path()
+                    } else {
+                        ".".to_owned()
+                    };
+                    tree::render(&node, &label, &opts)
+                };
+                tree.current = cur;
+
+                lines.iter().for_each(|line| println!("{}", line));
+                return Ok(());
+            }
+
+            let mut entries = if matches.is_present("recursive") {
+                tree.cur().list_r(full)
+            } else {
+                tree.cur().list(full)
+            };
 
             tree.current = cur;
 
-            Ok(())
+            if !format.is_text() {
+                let levels = notify::get_subscriptions(conf).await?;
+                for entry in entries.iter_mut() {
+                    let level = levels
+                        .get(&entry.id)
+                        .copied()
+                        .unwrap_or(ChannelSubscribeLevel::none);
+                    entry.subscription = Some(notify::level_name(level));
+                }
+            }
+
+            output::render(format, &entries)
         }
         "cd" => {
-            if let Some(ch_name) = matches.value_of("channel_name") {
-                let mut tree = get_channel_tree(conf).await?;
-                let path = Path::new(ch_name);
-                tree.go_path(path)?;
-                // RefCell::borrow(&tree.current)
-                //     .children
-                //     .iter()
-                //     .for_each(|ch| println!("{}", &RefCell::borrow(&ch).name));
-            } else {
-                todo!();
-            }
-            Ok(())
+            // like a shell, `cd` without a channel goes back to the root
+            let path = match matches.value_of("channel_name") {
+                Some(ch_name) => {
+                    let mut tree = get_channel_tree(conf).await?;
+                    enter_current_channel(&mut tree);
+                    tree.go_path(Path::new(ch_name))?;
+                    let node = tree.cur();
+                    if node.is_root() {
+                        "/".to_owned()
+                    } else {
+                        node.get_parent_full_path()
+                    }
+                }
+                None => "/".to_owned(),
+            };
+            store_current_channel(&path)
         }
+        "mkdir" => manage::mkdir(conf, matches).await,
+        "mv" => manage::mv(conf, matches).await,
+        "archive" => manage::set_archived(conf, matches, true).await,
+        "unarchive" => manage::set_archived(conf, matches, false).await,
+        "topic" => manage::topic(conf, matches).await,
+        "info" => manage::info(conf, matches).await,
+        "find" => find::find(conf, matches).await,
         x => {
             dbg!("{}", x);
             Ok(())
@@ -304,8 +485,53 @@ pub async fn channel(conf: &Configuration, matches: &ArgMatches<'_>, cmd: &str)
     }
 }
 
+const CHANNELS_CACHE: &str = "channels";
+const CURRENT_CHANNEL_FILE: &str = "current_channel";
+
 pub(crate) async fn get_channel_tree(conf: &Configuration) -> Result<ChannelTree> {
     let channels = apis::channel_api::get_channels(conf, None).await?;
+    // the cache only speeds up completions, so failing to write it is not an error
+    let _ = cache::store(CHANNELS_CACHE, &channels);
+    Ok(build_channel_tree(channels))
+}
+
+/// Builds the tree from the channels cached by the last `get_channel_tree`.
+pub(crate) fn get_cached_channel_tree() -> Result<ChannelTree> {
+    let channels: ChannelList = cache::load(CHANNELS_CACHE)?;
+    Ok(build_channel_tree(channels))
+}
+
+fn current_channel_path() -> Result<PathBuf> {
+    let mut path = config::dir_path()?;
+    path.push(CURRENT_CHANNEL_FILE);
+    Ok(path)
+}
+
+/// The path saved by `channel cd`, or `/` if it was never called.
+pub(crate) fn load_current_channel() -> String {
+    current_channel_path()
+        .and_then(|path| Ok(fs::read_to_string(path)?))
+        .map(|s| s.trim().to_owned())
+        .unwrap_or_else(|_| "/".to_owned())
+}
+
+fn store_current_channel(path: &str) -> Result<()> {
+    let file = current_channel_path()?;
+    if let Some(parent) = file.parent() {
+        DirBuilder::new().recursive(true).create(parent)?;
+    }
+    fs::write(&file, path).with_context(|| format!("cannot write {}", file.display()))
+}
+
+/// Moves to the channel saved by `channel cd`.
+/// Stays at the root if the saved channel was deleted or moved.
+fn enter_current_channel(tree: &mut ChannelTree) {
+    if tree.go_path(Path::new(&load_current_channel())).is_err() {
+        tree.go_root();
+    }
+}
+
+pub(crate) fn build_channel_tree(channels: ChannelList) -> ChannelTree {
     let root_channel_ids: Vec<ChannelId> = channels
         .public
         .iter()
@@ -318,9 +544,50 @@ pub(crate) async fn get_channel_tree(conf: &Configuration) -> Result<ChannelTree
     let dummy_channel = ChannelLike::new("".to_owned(), "dummy", None, root_channel_ids, false);
     let p = ChannelTreeNode::dummy();
     let p = Rc::downgrade(&Rc::new(RefCell::new(p)));
-    let tree = ChannelTree::new(construct_tree(p, dummy_channel, &mp));
+    ChannelTree::new(construct_tree(p, dummy_channel, &mp))
+}
 
-    Ok(tree)
+/// Channel paths starting with `word`. Paths of channels with children end with `/`.
+pub(crate) fn complete_path(tree: &mut ChannelTree, word: &str) -> Vec<String> {
+    let (dir, prefix) = match word.rfind('/') {
+        Some(i) => word.split_at(i + 1),
+        None => ("", word),
+    };
+    let node = match tree.get(Path::new(dir)) {
+        Ok(node) => node,
+        Err(_) => return Vec::new(),
+    };
+
+    let node = RefCell::borrow(&node);
+    let mut paths: Vec<String> = node
+        .children
+        .iter()
+        .map(|ch| RefCell::borrow(ch))
+        .filter(|ch| ch.name.starts_with(prefix))
+        .map(|ch| {
+            let slash = if ch.children.is_empty() { "" } else { "/" };
+            format!("{}{}{}", dir, ch.name, slash)
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// The maximum `limit` of `get_activity_timeline`, which cannot be paged.
+const ACTIVITY_LIMIT: i32 = 50;
+
+/// Ranks channels by their latest message. The most recently active channel is 0.
+/// Only the `ACTIVITY_LIMIT` most recently active channels are ranked.
+async fn get_activity_ranks(conf: &Configuration) -> Result<BTreeMap<ChannelId, usize>> {
+    let messages =
+        activity_api::get_activity_timeline(conf, Some(ACTIVITY_LIMIT), Some(true), Some(true))
+            .await
+            .with_context(|| "cannot get activity timeline")?;
+    let mut ranks = BTreeMap::new();
+    for (i, msg) in messages.into_iter().enumerate() {
+        ranks.entry(msg.channel_id).or_insert(i);
+    }
+    Ok(ranks)
 }
 
 pub(crate) fn get_channels_mp(channels: ChannelList) -> BTreeMap<ChannelId, ChannelLike> {
@@ -331,3 +598,43 @@ pub(crate) fn get_channels_mp(channels: ChannelList) -> BTreeMap<ChannelId, Chan
         .collect();
     mp
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> ChannelTree {
+        let channels = vec![
+            ChannelLike::new("a".to_owned(), "team", None, vec!["b".to_owned()], false),
+            ChannelLike::new("b".to_owned(), "SysAd", Some("a".to_owned()), vec![], false),
+            ChannelLike::new("c".to_owned(), "test", None, vec![], false),
+        ];
+        let mp: BTreeMap<ChannelId, ChannelLike> =
+            channels.into_iter().map(|ch| (ch.id.clone(), ch)).collect();
+        let root = ChannelLike::new(
+            "".to_owned(),
+            "dummy",
+            None,
+            vec!["a".to_owned(), "c".to_owned()],
+            false,
+        );
+        ChannelTree::new(construct_tree(Weak::new(), root, &mp))
+    }
+
+    #[test]
+    fn test_name_to_id() {
+        let mut tree = tree();
+        assert_eq!(tree.name_to_id(Path::new("/team/SysAd")).unwrap(), "b");
+        assert_eq!(tree.name_to_id(Path::new("./team/../test")).unwrap(), "c");
+        assert!(tree.name_to_id(Path::new("/team/bot")).is_err());
+    }
+
+    #[test]
+    fn test_complete_path() {
+        let mut tree = tree();
+        assert_eq!(complete_path(&mut tree, "/te"), vec!["/team/", "/test"]);
+        assert_eq!(complete_path(&mut tree, "/team/S"), vec!["/team/SysAd"]);
+        assert_eq!(complete_path(&mut tree, "te"), vec!["team/", "test"]);
+        assert!(complete_path(&mut tree, "/nothing/").is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeSet;
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, star_api},
+    models::ChannelSubscribeLevel,
+};
+use serde::{Serialize, Serializer};
+
+use super::{get_channel_tree, ChannelId};
+use crate::{
+    cli::{
+        notify,
+        output::{self, OutputFormat, Render},
+        picker,
+    },
+    utils::fuzzy,
+};
+
+const STAR_BOOST: i64 = 60;
+const NOTIFIED_BOOST: i64 = 40;
+const SUBSCRIBED_BOOST: i64 = 20;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FoundChannel {
+    pub id: ChannelId,
+    pub path: String,
+    pub archived: bool,
+    pub starred: bool,
+    #[serde(serialize_with = "serialize_level")]
+    pub subscription: ChannelSubscribeLevel,
+}
+
+/// Levels are written as `level_name` shows them.
+fn serialize_level<S: Serializer>(level: &ChannelSubscribeLevel, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(notify::level_name(*level))
+}
+
+impl FoundChannel {
+    fn boost(&self) -> i64 {
+        let mut boost = 0;
+        if self.starred {
+            boost += STAR_BOOST;
+        }
+        match self.subscription {
+            ChannelSubscribeLevel::notified => boost += NOTIFIED_BOOST,
+            ChannelSubscribeLevel::subscribed => boost += SUBSCRIBED_BOOST,
+            ChannelSubscribeLevel::none => {}
+        }
+        boost
+    }
+}
+
+impl Render for FoundChannel {
+    fn header() -> &'static [&'static str] {
+        &["id", "path", "archived", "starred", "subscription"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.path.clone(),
+            self.archived.to_string(),
+            self.starred.to_string(),
+            notify::level_name(self.subscription).to_owned(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        let mut line = self.path.clone();
+        if self.starred {
+            line += " *";
+        }
+        if self.subscription != ChannelSubscribeLevel::none {
+            line += &format!(" ({})", notify::level_name(self.subscription));
+        }
+        line
+    }
+}
+
+pub async fn find(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let query = matches.value_of("query").unwrap_or("");
+    let limit: usize = matches
+        .value_of("limit")
+        .unwrap_or("20")
+        .parse()
+        .with_context(|| "limit must be a number")?;
+
+    let entries = get_channel_tree(conf).await?.root().list_r(true);
+    let stars: BTreeSet<ChannelId> = star_api::get_my_stars(conf)
+        .await
+        .with_context(|| "cannot get stars")?
+        .into_iter()
+        .collect();
+    let levels = notify::get_subscriptions(conf).await?;
+
+    let include_archived = matches.is_present("archived");
+    let candidates: Vec<FoundChannel> = entries
+        .into_iter()
+        .filter(|e| include_archived || !e.archived)
+        .map(|e| FoundChannel {
+            starred: stars.contains(&e.id),
+            subscription: levels
+                .get(&e.id)
+                .copied()
+                .unwrap_or(ChannelSubscribeLevel::none),
+            id: e.id,
+            path: e.path,
+            archived: e.archived,
+        })
+        .collect();
+
+    let rank = |query: &str| -> Vec<usize> {
+        let mut ranked: Vec<(usize, i64)> =
+            fuzzy::rank(query, candidates.iter().map(|c| c.path.as_str()))
+                .into_iter()
+                .map(|(i, score)| (i, score + candidates[i].boost()))
+                .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        ranked.into_iter().map(|(i, _)| i).collect()
+    };
+
+    let selected: Vec<usize> = if matches.is_present("interactive") {
+        let paths: Vec<String> = candidates.iter().map(|c| c.path.clone()).collect();
+        match picker::pick("find channel", &paths, query, rank)? {
+            Some(i) => vec![i],
+            None => return Ok(()),
+        }
+    } else {
+        rank(query).into_iter().take(limit).collect()
+    };
+
+    if selected.is_empty() {
+        bail!("no channel matches '{}'", query);
+    }
+
+    let found: Vec<FoundChannel> = selected
+        .into_iter()
+        .map(|i| candidates[i].clone())
+        .collect();
+    if matches.is_present("print-id") {
+        found.iter().for_each(|c| println!("{}", c.id));
+    } else if matches.is_present("print-path") {
+        found.iter().for_each(|c| println!("{}", c.path));
+    } else {
+        output::render(format, &found)?;
+    }
+
+    Ok(())
+}
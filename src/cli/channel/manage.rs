@@ -0,0 +1,340 @@
+use std::{
+    cell::RefCell,
+    ffi::OsStr,
+    io::{self, Read},
+    path::Path,
+    rc::Rc,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{channel_api, configuration::Configuration, user_api},
+    models::{PatchChannelRequest, PostChannelRequest, PutChannelTopicRequest},
+};
+use serde::Serialize;
+
+use super::{get_channel_tree, ChannelId, ChannelTreeNode};
+use crate::cli::output::{self, OutputFormat, Render};
+
+/// traQ moves a channel to the root when this id is given as its parent.
+const ROOT_PARENT_ID: &str = "00000000-0000-0000-0000-000000000000";
+const EVENTS_LIMIT: i32 = 200;
+/// `find_creator` gives up after this many pages of events.
+const MAX_EVENT_PAGES: i32 = 10;
+
+pub async fn mkdir(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let path = Path::new(matches.value_of("channel_name").unwrap());
+    let mut tree = get_channel_tree(conf).await?;
+
+    let mut missing = Vec::new();
+    let mut existing = path;
+    let parent = loop {
+        match tree.get(existing) {
+            Ok(node) => break node,
+            Err(_) => {
+                let name = existing
+                    .file_name()
+                    .and_then(OsStr::to_str)
+                    .with_context(|| format!("invalid channel name: {}", existing.display()))?;
+                missing.push(name.to_owned());
+                existing = existing
+                    .parent()
+                    .with_context(|| format!("{} is not found", existing.display()))?;
+            }
+        }
+    };
+
+    if missing.is_empty() {
+        bail!("{} already exists", path.display());
+    }
+    if missing.len() > 1 && !matches.is_present("parents") {
+        bail!(
+            "{} is not found",
+            existing.join(missing.last().unwrap()).display()
+        );
+    }
+
+    let (mut parent_id, mut parent_path) = {
+        let parent = RefCell::borrow(&parent);
+        (id_of(&parent), parent.get_parent_full_path())
+    };
+    for name in missing.into_iter().rev() {
+        let req = PostChannelRequest::new(name.clone(), parent_id);
+        let ch = channel_api::create_channel(conf, Some(req))
+            .await
+            .with_context(|| format!("cannot create {}/{}", parent_path, name))?;
+        parent_path = format!("{}/{}", parent_path, name);
+        println!("created {} ({})", parent_path, ch.id);
+        parent_id = Some(ch.id);
+    }
+
+    Ok(())
+}
+
+pub async fn mv(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let src = Path::new(matches.value_of("src").unwrap());
+    let dst = Path::new(matches.value_of("dst").unwrap());
+    let mut tree = get_channel_tree(conf).await?;
+
+    let src = tree.get(src)?;
+    let (id, req, old_path, new_path) = {
+        let src = RefCell::borrow(&src);
+        if src.is_root() {
+            bail!("cannot move root");
+        }
+
+        // like `mv`, an existing destination becomes the new parent
+        let (name, parent_node) = match tree.get(dst) {
+            Ok(node) => (src.name.clone(), node),
+            Err(_) => {
+                let name = dst
+                    .file_name()
+                    .and_then(OsStr::to_str)
+                    .with_context(|| format!("invalid channel name: {}", dst.display()))?;
+                let parent = tree.get(dst.parent().unwrap_or_else(|| Path::new("/")))?;
+                (name.to_owned(), parent)
+            }
+        };
+
+        let same_parent = matches!(src.parent.upgrade(), Some(p) if Rc::ptr_eq(&p, &parent_node));
+        let old_path = src.get_parent_full_path();
+        let parent = RefCell::borrow(&parent_node);
+        let new_parent_path = parent.get_parent_full_path();
+        if new_parent_path == old_path || new_parent_path.starts_with(&format!("{}/", old_path)) {
+            bail!("cannot move {} into itself", old_path);
+        }
+        if parent
+            .children
+            .iter()
+            .any(|ch| RefCell::borrow(ch).name == name)
+        {
+            bail!("{}/{} already exists", new_parent_path, name);
+        }
+
+        let mut req = PatchChannelRequest::new();
+        if name != src.name {
+            req.name = Some(name.clone());
+        }
+        if !same_parent {
+            req.parent = Some(id_of(&parent).unwrap_or_else(|| ROOT_PARENT_ID.to_owned()));
+        }
+        let new_path = format!("{}/{}", new_parent_path, name);
+        (src.id.clone(), req, old_path, new_path)
+    };
+
+    channel_api::edit_channel(conf, &id, Some(req))
+        .await
+        .with_context(|| format!("cannot move {}", old_path))?;
+    println!("{} -> {}", old_path, new_path);
+
+    Ok(())
+}
+
+pub async fn set_archived(
+    conf: &Configuration,
+    matches: &ArgMatches<'_>,
+    archived: bool,
+) -> Result<()> {
+    let mut tree = get_channel_tree(conf).await?;
+    let channels = matches
+        .values_of("channel_names")
+        .unwrap()
+        .map(|v| {
+            let node = tree.get(Path::new(v))?;
+            let node = RefCell::borrow(&node);
+            match id_of(&node) {
+                Some(id) => Ok((id, node.get_parent_full_path())),
+                None => bail!("cannot archive root"),
+            }
+        })
+        .collect::<Result<Vec<(ChannelId, String)>>>()?;
+
+    for (id, path) in channels {
+        let mut req = PatchChannelRequest::new();
+        req.archived = Some(archived);
+        channel_api::edit_channel(conf, &id, Some(req))
+            .await
+            .with_context(|| format!("cannot change {}", path))?;
+        println!(
+            "{} {}",
+            if archived { "archived" } else { "unarchived" },
+            path
+        );
+    }
+
+    Ok(())
+}
+
+pub async fn topic(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let (cmd, matches) = match matches.subcommand() {
+        (cmd, Some(m)) => (cmd, m),
+        _ => bail!("specify get or set"),
+    };
+
+    let mut tree = get_channel_tree(conf).await?;
+    let id = tree.name_to_id(Path::new(matches.value_of("channel_name").unwrap()))?;
+
+    match cmd {
+        "get" => {
+            let topic = channel_api::get_channel_topic(conf, &id)
+                .await
+                .with_context(|| "cannot get topic")?;
+            println!("{}", topic.topic);
+        }
+        "set" => {
+            let topic = match matches.value_of("topic") {
+                Some(topic) => topic.to_owned(),
+                None => {
+                    let mut topic = String::new();
+                    io::stdin().read_to_string(&mut topic)?;
+                    topic.trim_end().to_owned()
+                }
+            };
+            let req = PutChannelTopicRequest::new(topic);
+            channel_api::edit_channel_topic(conf, &id, Some(req))
+                .await
+                .with_context(|| "cannot set topic")?;
+        }
+        x => bail!("unknown topic command '{}'", x),
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChannelInfo {
+    pub id: ChannelId,
+    pub name: String,
+    pub path: String,
+    pub topic: String,
+    pub creator: Option<String>,
+    pub children: usize,
+    pub archived: bool,
+    pub force: bool,
+}
+
+impl Render for ChannelInfo {
+    fn header() -> &'static [&'static str] {
+        &[
+            "id", "name", "path", "topic", "creator", "children", "archived", "force",
+        ]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.path.clone(),
+            self.topic.clone(),
+            self.creator.clone().unwrap_or_default(),
+            self.children.to_string(),
+            self.archived.to_string(),
+            self.force.to_string(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!(
+            "id:         {}\npath:       {}\ntopic:      {}\ncreator:    {}\nchildren:   {}\nvisibility: {}\nforce:      {}",
+            self.id,
+            self.path,
+            self.topic,
+            self.creator.as_deref().unwrap_or("unknown"),
+            self.children,
+            if self.archived { "archived" } else { "visible" },
+            self.force,
+        )
+    }
+}
+
+pub async fn info(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let mut tree = get_channel_tree(conf).await?;
+    let node = tree.get(Path::new(matches.value_of("channel_name").unwrap()))?;
+
+    let (id, path, parent_id, children) = {
+        let node = RefCell::borrow(&node);
+        if node.is_root() {
+            bail!("root is not a channel");
+        }
+        let parent_id = node
+            .parent
+            .upgrade()
+            .and_then(|p| id_of(&RefCell::borrow(&p)));
+        (
+            node.id.clone(),
+            node.get_parent_full_path(),
+            parent_id,
+            node.children.len(),
+        )
+    };
+
+    let ch = channel_api::get_channel(conf, &id)
+        .await
+        .with_context(|| format!("cannot get {}", path))?;
+    let creator = match parent_id {
+        Some(parent_id) => find_creator(conf, &parent_id, &id).await?,
+        None => None,
+    };
+
+    let info = ChannelInfo {
+        id,
+        name: ch.name,
+        path,
+        topic: ch.topic,
+        creator,
+        children,
+        archived: ch.archived,
+        force: ch.force,
+    };
+    output::render(format, &[info])
+}
+
+/// traQ records who created a channel only as a `ChildCreated` event of its parent,
+/// so the creator of a top-level channel is unknown.
+async fn find_creator(
+    conf: &Configuration,
+    parent_id: &str,
+    channel_id: &str,
+) -> Result<Option<String>> {
+    for page in 0..MAX_EVENT_PAGES {
+        let events = channel_api::get_channel_events(
+            conf,
+            parent_id,
+            Some(EVENTS_LIMIT),
+            Some(page * EVENTS_LIMIT),
+            None,
+            None,
+            None,
+            Some("asc"),
+        )
+        .await
+        .with_context(|| "cannot get channel events")?;
+
+        for event in events.iter() {
+            let event = serde_json::to_value(event)?;
+            if event["type"] == "ChildCreated" && event["detail"]["channelId"] == channel_id {
+                if let Some(user_id) = event["detail"]["userId"].as_str() {
+                    let user = user_api::get_user(conf, user_id)
+                        .await
+                        .with_context(|| "cannot get user")?;
+                    return Ok(Some(format!("@{}", user.name)));
+                }
+            }
+        }
+
+        if events.len() < EVENTS_LIMIT as usize {
+            break;
+        }
+    }
+    Ok(None)
+}
+
+fn id_of(node: &ChannelTreeNode) -> Option<ChannelId> {
+    if node.is_root() {
+        None
+    } else {
+        Some(node.id.clone())
+    }
+}
@@ -0,0 +1,228 @@
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc, str::FromStr};
+
+use anyhow::{bail, Result};
+use rust_traq::models::ChannelSubscribeLevel;
+use termion::color;
+
+use super::{ChannelId, ChannelTreeNode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Activity,
+}
+
+impl SortKey {
+    pub const VALUES: &'static [&'static str] = &["name", "activity"];
+}
+
+impl FromStr for SortKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "name" => Ok(Self::Name),
+            "activity" => Ok(Self::Activity),
+            x => bail!("unknown sort key '{}'", x),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct TreeOptions {
+    /// Maximum depth to expand. Deeper channels are collapsed into a count of their descendants.
+    pub depth: Option<usize>,
+    pub color: bool,
+    pub sort: SortKey,
+    pub levels: BTreeMap<ChannelId, ChannelSubscribeLevel>,
+    /// Recency rank of each channel. Smaller is more recent.
+    pub activity: BTreeMap<ChannelId, usize>,
+}
+
+impl TreeOptions {
+    pub fn new(sort: SortKey) -> Self {
+        Self {
+            depth: None,
+            color: false,
+            sort,
+            levels: BTreeMap::new(),
+            activity: BTreeMap::new(),
+        }
+    }
+}
+
+/// Renders `node` and its descendants like the `tree` command.
+pub fn render(node: &ChannelTreeNode, label: &str, opts: &TreeOptions) -> Vec<String> {
+    let mut activity = BTreeMap::new();
+    if opts.sort == SortKey::Activity {
+        subtree_activity(node, &opts.activity, &mut activity);
+    }
+
+    let mut lines = vec![label.to_owned()];
+    let mut count = 0;
+    walk(node, "", 0, opts, &activity, &mut lines, &mut count);
+    lines.push(String::new());
+    lines.push(format!(
+        "{} channel{}",
+        count,
+        if count == 1 { "" } else { "s" }
+    ));
+    lines
+}
+
+fn walk(
+    node: &ChannelTreeNode,
+    prefix: &str,
+    depth: usize,
+    opts: &TreeOptions,
+    activity: &BTreeMap<ChannelId, usize>,
+    lines: &mut Vec<String>,
+    count: &mut usize,
+) {
+    let children = sorted_children(node, opts.sort, activity);
+    let len = children.len();
+    for (i, ch) in children.iter().enumerate() {
+        let ch = RefCell::borrow(ch);
+        let (branch, indent) = if i + 1 == len {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        *count += 1;
+
+        let collapsed = matches!(opts.depth, Some(d) if depth + 1 >= d) && !ch.children.is_empty();
+        let mut label = paint(&ch, opts);
+        if collapsed {
+            let hidden = descendants(&ch);
+            *count += hidden;
+            label += &format!(" (+{})", hidden);
+        }
+        lines.push(format!("{}{}{}", prefix, branch, label));
+
+        if !collapsed {
+            let prefix = format!("{}{}", prefix, indent);
+            walk(&ch, &prefix, depth + 1, opts, activity, lines, count);
+        }
+    }
+}
+
+fn sorted_children(
+    node: &ChannelTreeNode,
+    sort: SortKey,
+    activity: &BTreeMap<ChannelId, usize>,
+) -> Vec<Rc<RefCell<ChannelTreeNode>>> {
+    let mut children = node.children.clone();
+    children.sort_by(|a, b| {
+        let (a, b) = (RefCell::borrow(a), RefCell::borrow(b));
+        match sort {
+            SortKey::Name => a.name.cmp(&b.name),
+            SortKey::Activity => {
+                let (ra, rb) = (activity.get(&a.id), activity.get(&b.id));
+                (ra.is_none(), ra, &a.name).cmp(&(rb.is_none(), rb, &b.name))
+            }
+        }
+    });
+    children
+}
+
+/// Number of channels under `node`, not counting itself.
+fn descendants(node: &ChannelTreeNode) -> usize {
+    node.children
+        .iter()
+        .map(|ch| 1 + descendants(&RefCell::borrow(ch)))
+        .sum()
+}
+
+/// Collects the most recent rank within each subtree, so parents of active channels come first.
+fn subtree_activity(
+    node: &ChannelTreeNode,
+    ranks: &BTreeMap<ChannelId, usize>,
+    out: &mut BTreeMap<ChannelId, usize>,
+) -> Option<usize> {
+    let mut best = ranks.get(&node.id).copied();
+    for ch in node.children.iter() {
+        if let Some(rank) = subtree_activity(&RefCell::borrow(ch), ranks, out) {
+            best = Some(best.map_or(rank, |b| b.min(rank)));
+        }
+    }
+    if let Some(rank) = best {
+        out.insert(node.id.clone(), rank);
+    }
+    best
+}
+
+fn paint(node: &ChannelTreeNode, opts: &TreeOptions) -> String {
+    if !opts.color {
+        return node.name.clone();
+    }
+
+    let level = opts
+        .levels
+        .get(&node.id)
+        .copied()
+        .unwrap_or(ChannelSubscribeLevel::none);
+    let reset = color::Fg(color::Reset);
+    if node.archived {
+        format!("{}{}{}", color::Fg(color::LightBlack), node.name, reset)
+    } else {
+        match level {
+            ChannelSubscribeLevel::notified => {
+                format!("{}{}{}", color::Fg(color::Yellow), node.name, reset)
+            }
+            ChannelSubscribeLevel::subscribed => {
+                format!("{}{}{}", color::Fg(color::Green), node.name, reset)
+            }
+            ChannelSubscribeLevel::none => node.name.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::channel::{construct_tree, ChannelLike};
+    use std::rc::Weak;
+
+    fn node() -> Rc<RefCell<ChannelTreeNode>> {
+        let channels = vec![
+            ChannelLike::new(
+                "r".to_owned(),
+                "",
+                None,
+                vec!["b".to_owned(), "a".to_owned()],
+                false,
+            ),
+            ChannelLike::new("a".to_owned(), "a", None, vec!["c".to_owned()], false),
+            ChannelLike::new("b".to_owned(), "b", None, vec![], false),
+            ChannelLike::new("c".to_owned(), "c", Some("a".to_owned()), vec![], false),
+        ];
+        let mp: BTreeMap<ChannelId, ChannelLike> =
+            channels.into_iter().map(|ch| (ch.id.clone(), ch)).collect();
+        construct_tree(Weak::new(), mp["r"].clone(), &mp)
+    }
+
+    #[test]
+    fn test_render() {
+        let root = node();
+        let lines = render(
+            &RefCell::borrow(&root),
+            "/",
+            &TreeOptions::new(SortKey::Name),
+        );
+        assert_eq!(
+            lines,
+            vec!["/", "├── a", "│   └── c", "└── b", "", "3 channels"]
+        );
+    }
+
+    #[test]
+    fn test_render_depth_and_activity() {
+        let root = node();
+        let mut opts = TreeOptions::new(SortKey::Activity);
+        opts.depth = Some(1);
+        opts.activity.insert("b".to_owned(), 1);
+        opts.activity.insert("c".to_owned(), 0);
+        let lines = render(&RefCell::borrow(&root), "/", &opts);
+        assert_eq!(lines, vec!["/", "├── a (+1)", "└── b", "", "3 channels"]);
+    }
+}
@@ -1,4 +1,6 @@
-use clap::{App, Arg, SubCommand};
+use clap::{App, AppSettings, Arg, ArgGroup, SubCommand};
+
+use super::{channel::SortKey, export::ExportFormat, notify::LEVELS, output::OutputFormat};
 
 const BANNER: &str = "
             _ 
@@ -9,6 +11,12 @@ const BANNER: &str = "
 ";
 
 pub fn clap_app() -> App<'static, 'static> {
+    completion_app().subcommand(complete_subcommand())
+}
+
+/// The app without the hidden `__complete` command.
+/// clap's bash generator splits subcommand paths on `__` and panics on it.
+pub fn completion_app() -> App<'static, 'static> {
     let clap_app = App::new(env!("CARGO_PKG_NAME"))
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
@@ -29,13 +37,46 @@ pub fn clap_app() -> App<'static, 'static> {
             Arg::with_name("show-config")
                 .long("show-config")
                 .help("show config"),
+            Arg::with_name("output")
+                .long("output")
+                .help("output format")
+                .takes_value(true)
+                .possible_values(OutputFormat::VALUES)
+                .default_value("text")
+                .value_name("FORMAT")
+                .global(true),
         ])
         .subcommand(channel::channel_subcommand())
-        .subcommand(notify::notify_subcommand());
+        .subcommand(notify::notify_subcommand())
+        .subcommand(msg::msg_subcommand())
+        .subcommand(search::search_subcommand())
+        .subcommand(export::export_subcommand())
+        .subcommand(file::file_subcommand())
+        .subcommand(stamp::stamp_subcommand())
+        .subcommand(user::user_subcommand())
+        .subcommand(group::group_subcommand())
+        .subcommand(me::me_subcommand())
+        .subcommand(unread::unread_subcommand())
+        .subcommand(pin::pin_subcommand())
+        .subcommand(star::star_subcommand())
+        .subcommand(clip::clip_subcommand());
 
     clap_app
 }
 
+fn complete_subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("__complete")
+        .about("print completions for shell scripts")
+        .setting(AppSettings::Hidden)
+        .arg(
+            Arg::with_name("kind")
+                .help("kind of word to complete")
+                .possible_values(&["channel"])
+                .required(true),
+        )
+        .arg(Arg::with_name("word").help("partial word"))
+}
+
 mod channel {
     use super::*;
 
@@ -48,6 +89,13 @@ mod channel {
             .visible_alias("ch")
             .subcommand(list())
             .subcommand(cd())
+            .subcommand(mkdir())
+            .subcommand(mv())
+            .subcommand(archive("archive", "archive channels"))
+            .subcommand(archive("unarchive", "unarchive channels"))
+            .subcommand(topic())
+            .subcommand(info())
+            .subcommand(find())
     }
 
     fn list() -> App<'static, 'static> {
@@ -69,7 +117,40 @@ mod channel {
                     .long("full-path")
                     .help("show full path of channel"),
             )
-            .arg(Arg::with_name("channel_name").help("specify channel name"))
+            .arg(
+                Arg::with_name("tree")
+                    .short("t")
+                    .long("tree")
+                    .help("show channel recursively as a tree"),
+            )
+            .arg(
+                Arg::with_name("depth")
+                    .long("depth")
+                    .help("max depth of tree. deeper channels are collapsed")
+                    .takes_value(true)
+                    .value_name("N")
+                    .validator(|v| match v.parse::<usize>() {
+                        Ok(0) => Err("depth must be at least 1".to_owned()),
+                        Ok(_) => Ok(()),
+                        Err(e) => Err(e.to_string()),
+                    }),
+            )
+            .arg(
+                Arg::with_name("color")
+                    .long("color")
+                    .help("color archived and subscribed channels in tree"),
+            )
+            .arg(
+                Arg::with_name("sort")
+                    .long("sort")
+                    .help("sort order of tree, name by default. activity ranks the 50 most recently active channels and puts the rest after them by name")
+                    .takes_value(true)
+                    .possible_values(SortKey::VALUES),
+            )
+            .arg(
+                Arg::with_name("channel_name")
+                    .help("specify channel name. the current channel if omitted"),
+            )
     }
 
     fn cd() -> App<'static, 'static> {
@@ -77,14 +158,148 @@ mod channel {
             .version(env!("CARGO_PKG_VERSION"))
             .author(env!("CARGO_PKG_AUTHORS"))
             .about("change channel")
-            .long_about("change current channel")
+            .long_about(
+                "change current channel. it is used when a command such as msg post is run without a channel, and relative names given to cd are resolved from it. channel names of other commands are always resolved from the root. go back to the root if no channel is given.",
+            )
             .arg(
                 Arg::with_name("channel_name")
                     .help("channel name")
-                    .required(true)
                     .multiple(false),
             )
     }
+
+    fn mkdir() -> App<'static, 'static> {
+        SubCommand::with_name("mkdir")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("create channel")
+            .long_about("create a channel under the parent path")
+            .arg(
+                Arg::with_name("parents")
+                    .short("p")
+                    .long("parents")
+                    .help("create parent channels as needed"),
+            )
+            .arg(
+                Arg::with_name("channel_name")
+                    .help("channel name")
+                    .required(true),
+            )
+    }
+
+    fn mv() -> App<'static, 'static> {
+        SubCommand::with_name("mv")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("rename or move channel")
+            .long_about("rename or move channel. if destination exists, channel is moved under it.")
+            .arg(
+                Arg::with_name("src")
+                    .help("source channel name")
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("dst")
+                    .help("destination channel name")
+                    .required(true),
+            )
+    }
+
+    fn archive(name: &'static str, about: &'static str) -> App<'static, 'static> {
+        SubCommand::with_name(name)
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about(about)
+            .arg(
+                Arg::with_name("channel_names")
+                    .help("channel names")
+                    .required(true)
+                    .multiple(true),
+            )
+    }
+
+    fn topic() -> App<'static, 'static> {
+        SubCommand::with_name("topic")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("get or set channel topic")
+            .subcommand(
+                SubCommand::with_name("get")
+                    .about("show channel topic")
+                    .arg(
+                        Arg::with_name("channel_name")
+                            .help("channel name")
+                            .required(true),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("set")
+                    .about("set channel topic")
+                    .arg(
+                        Arg::with_name("channel_name")
+                            .help("channel name")
+                            .required(true),
+                    )
+                    .arg(Arg::with_name("topic").help("new topic. read from stdin if omitted")),
+            )
+    }
+
+    fn find() -> App<'static, 'static> {
+        SubCommand::with_name("find")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("find channel")
+            .long_about(
+                "fuzzy find channel by full path. starred and subscribed channels come first.",
+            )
+            .arg(
+                Arg::with_name("interactive")
+                    .short("i")
+                    .long("interactive")
+                    .help("choose channel interactively"),
+            )
+            .arg(
+                Arg::with_name("print-id")
+                    .long("print-id")
+                    .help("print only channel ids")
+                    .conflicts_with("print-path"),
+            )
+            .arg(
+                Arg::with_name("print-path")
+                    .long("print-path")
+                    .help("print only channel paths"),
+            )
+            .arg(
+                Arg::with_name("archived")
+                    .short("a")
+                    .long("archived")
+                    .help("include archived channels"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .short("n")
+                    .long("limit")
+                    .help("max number of results")
+                    .takes_value(true)
+                    .default_value("20"),
+            )
+            .arg(Arg::with_name("query").help("search query"))
+    }
+
+    fn info() -> App<'static, 'static> {
+        SubCommand::with_name("info")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("show channel information")
+            .long_about(
+                "show channel information. the creator is looked up in the events of the parent channel, so it is unknown for top-level channels and for channels whose parent has too many events.",
+            )
+            .arg(
+                Arg::with_name("channel_name")
+                    .help("channel name")
+                    .required(true),
+            )
+    }
 }
 
 mod notify {
@@ -97,12 +312,18 @@ mod notify {
             .about("notification api")
             .long_about("This command manipulate notification api.")
             .visible_alias("notif")
+            .setting(AppSettings::SubcommandsNegateReqs)
+            .setting(AppSettings::ArgsNegateSubcommands)
             .arg(
                 Arg::with_name("level")
                     // .short("l")
                     // .long("level")
-                    .help("notification level. 0: not subscribe, 1: unread only, 2: on")
+                    .help(
+                        "notification level. none: not subscribe, unread: unread only, on: notify",
+                    )
                     // .takes_value(true)
+                    .possible_values(LEVELS)
+                    .hide_possible_values(true)
                     .multiple(false)
                     .required(true),
             )
@@ -113,5 +334,1189 @@ mod notify {
                     .takes_value(true)
                     .multiple(true), // .required(true),
             )
+            .arg(
+                Arg::with_name("recursive")
+                    .short("r")
+                    .long("recursive")
+                    .help("apply to descendant channels too"),
+            )
+            .arg(
+                Arg::with_name("exclude")
+                    .short("e")
+                    .long("exclude")
+                    .help("exclude channel and its descendants. glob is allowed")
+                    .takes_value(true)
+                    .value_name("PATH")
+                    .multiple(true)
+                    .number_of_values(1),
+            )
+            .arg(
+                Arg::with_name("skip-archived")
+                    .long("skip-archived")
+                    .help("skip archived channels"),
+            )
+            .arg(
+                Arg::with_name("dry-run")
+                    .short("n")
+                    .long("dry-run")
+                    .help("print the changes without applying them"),
+            )
+            .subcommand(show())
+            .subcommand(export())
+            .subcommand(apply())
+            .subcommand(backup())
+            .subcommand(restore())
+    }
+
+    fn backup() -> App<'static, 'static> {
+        SubCommand::with_name("backup")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("save notification levels and stars")
+            .long_about(
+                "save notification levels and stars of all channels to $XDG_DATA_HOME/qui/backups/notify-<timestamp>.json (~/.local/share/qui/backups by default).",
+            )
+    }
+
+    fn restore() -> App<'static, 'static> {
+        SubCommand::with_name("restore")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("restore notification levels and stars from a backup")
+            .arg(
+                Arg::with_name("yes")
+                    .short("y")
+                    .long("yes")
+                    .help("restore without confirmation"),
+            )
+            .arg(Arg::with_name("file").help("backup file").required(true))
+    }
+
+    fn export() -> App<'static, 'static> {
+        SubCommand::with_name("export")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("export notification levels as toml")
+    }
+
+    fn apply() -> App<'static, 'static> {
+        SubCommand::with_name("apply")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("apply notification levels from toml")
+            .long_about(
+                "apply notification levels from toml. keys of [subscriptions] are channel paths or globs and values are none, unread or on. subscribed channels not in the file are unsubscribed.",
+            )
+            .arg(
+                Arg::with_name("yes")
+                    .short("y")
+                    .long("yes")
+                    .help("apply without confirmation"),
+            )
+            .arg(
+                Arg::with_name("file")
+                    .help("toml file")
+                    .required(true),
+            )
+    }
+
+    fn show() -> App<'static, 'static> {
+        SubCommand::with_name("show")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("show notification levels")
+            .long_about(
+                "show notification levels of channels. all subscribed channels are shown if no channel is specified.",
+            )
+            .arg(
+                Arg::with_name("recursive")
+                    .short("r")
+                    .long("recursive")
+                    .help("show descendant channels too"),
+            )
+            .arg(
+                Arg::with_name("channel_names")
+                    .help("specify channel names")
+                    .multiple(true),
+            )
+    }
+}
+
+mod msg {
+    use super::*;
+
+    pub fn msg_subcommand() -> App<'static, 'static> {
+        SubCommand::with_name("msg")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("message api")
+            .long_about("This command manipulate message api.")
+            .visible_alias("message")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(post())
+            .subcommand(ls())
+            .subcommand(tail())
+            .subcommand(edit())
+            .subcommand(rm())
+    }
+
+    fn edit() -> App<'static, 'static> {
+        SubCommand::with_name("edit")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("edit a message with $EDITOR")
+            .arg(
+                Arg::with_name("message")
+                    .help("message id or link")
+                    .required(true),
+            )
+    }
+
+    fn rm() -> App<'static, 'static> {
+        SubCommand::with_name("rm")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("delete messages")
+            .arg(
+                Arg::with_name("yes")
+                    .short("y")
+                    .long("yes")
+                    .help("delete without confirmation"),
+            )
+            .arg(
+                Arg::with_name("messages")
+                    .help("message ids or links")
+                    .required(true)
+                    .multiple(true),
+            )
+    }
+
+    fn tail() -> App<'static, 'static> {
+        SubCommand::with_name("tail")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("follow new messages")
+            .long_about(
+                "follow messages posted, edited and deleted in the channels, or in the current channel if none is given. it reconnects automatically. use --output jsonl for one json per event.",
+            )
+            .arg(
+                Arg::with_name("all")
+                    .short("a")
+                    .long("all")
+                    .help("follow all public channels")
+                    .conflicts_with_all(&["subscribed", "channel_names"]),
+            )
+            .arg(
+                Arg::with_name("subscribed")
+                    .short("s")
+                    .long("subscribed")
+                    .help("follow subscribed channels")
+                    .conflicts_with("channel_names"),
+            )
+            .arg(
+                Arg::with_name("channel_names")
+                    .help("channel names")
+                    .multiple(true),
+            )
+    }
+
+    fn ls() -> App<'static, 'static> {
+        SubCommand::with_name("ls")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("list messages")
+            .long_about(
+                "list the latest messages of the channel, or of the current channel if it is omitted. messages are shown oldest first.",
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .short("n")
+                    .long("limit")
+                    .help("max number of messages")
+                    .takes_value(true)
+                    .default_value("20"),
+            )
+            .arg(
+                Arg::with_name("since")
+                    .long("since")
+                    .help("only messages after this time (RFC 3339)")
+                    .takes_value(true)
+                    .value_name("TIME"),
+            )
+            .arg(
+                Arg::with_name("until")
+                    .long("until")
+                    .help("only messages before this time (RFC 3339)")
+                    .takes_value(true)
+                    .value_name("TIME"),
+            )
+            .arg(
+                Arg::with_name("before")
+                    .long("before")
+                    .help("only messages before this message. id or link")
+                    .takes_value(true)
+                    .value_name("MESSAGE"),
+            )
+            .arg(
+                Arg::with_name("reverse")
+                    .short("r")
+                    .long("reverse")
+                    .help("show newest first"),
+            )
+            .arg(Arg::with_name("channel_name").help("channel name"))
+    }
+
+    fn post() -> App<'static, 'static> {
+        SubCommand::with_name("post")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("post a message")
+            .long_about(
+                "post a message to the channel, or to the current channel if it is omitted. the text is read from stdin or $EDITOR if it is omitted.",
+            )
+            .arg(
+                Arg::with_name("embed")
+                    .long("embed")
+                    .help("turn @user and #channel into embeds"),
+            )
+            .arg(Arg::with_name("channel_name").help("channel name"))
+            .arg(Arg::with_name("text").help("message text"))
+    }
+}
+
+mod search {
+    use super::*;
+
+    pub fn search_subcommand() -> App<'static, 'static> {
+        SubCommand::with_name("search")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("search messages")
+            .arg(
+                Arg::with_name("in")
+                    .long("in")
+                    .help("only messages in this channel")
+                    .takes_value(true)
+                    .value_name("CHANNEL"),
+            )
+            .arg(
+                Arg::with_name("from")
+                    .long("from")
+                    .help("only messages from this user")
+                    .takes_value(true)
+                    .value_name("@USER"),
+            )
+            .arg(
+                Arg::with_name("after")
+                    .long("after")
+                    .help("only messages after this time (RFC 3339)")
+                    .takes_value(true)
+                    .value_name("TIME"),
+            )
+            .arg(
+                Arg::with_name("before")
+                    .long("before")
+                    .help("only messages before this time (RFC 3339)")
+                    .takes_value(true)
+                    .value_name("TIME"),
+            )
+            .arg(
+                Arg::with_name("has-attachment")
+                    .long("has-attachment")
+                    .help("only messages with attachments"),
+            )
+            .arg(
+                Arg::with_name("bot")
+                    .long("bot")
+                    .help("only messages from bots")
+                    .conflicts_with("no-bot"),
+            )
+            .arg(
+                Arg::with_name("no-bot")
+                    .long("no-bot")
+                    .help("exclude messages from bots"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .short("n")
+                    .long("limit")
+                    .help("max number of results")
+                    .takes_value(true)
+                    .default_value("20"),
+            )
+            .arg(
+                Arg::with_name("offset")
+                    .long("offset")
+                    .help("skip this number of results")
+                    .takes_value(true)
+                    .default_value("0"),
+            )
+            .arg(Arg::with_name("query").help("search words").required(true))
+    }
+}
+
+mod export {
+    use super::*;
+
+    pub fn export_subcommand() -> App<'static, 'static> {
+        SubCommand::with_name("export")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("export channel history")
+            .long_about(
+                "export messages of channels to one file per channel. running it again appends messages posted since the last export.",
+            )
+            .arg(
+                Arg::with_name("recursive")
+                    .short("r")
+                    .long("recursive")
+                    .help("export descendant channels too"),
+            )
+            .arg(
+                Arg::with_name("format")
+                    .short("f")
+                    .long("format")
+                    .help("file format")
+                    .takes_value(true)
+                    .possible_values(ExportFormat::VALUES)
+                    .default_value("jsonl"),
+            )
+            .arg(
+                Arg::with_name("out")
+                    .short("o")
+                    .long("out")
+                    .help("output directory")
+                    .takes_value(true)
+                    .value_name("DIR")
+                    .default_value("."),
+            )
+            .arg(
+                Arg::with_name("attachments")
+                    .long("attachments")
+                    .help("download attached files"),
+            )
+            .arg(
+                Arg::with_name("channel_name")
+                    .help("channel name")
+                    .required(true),
+            )
+    }
+}
+
+mod file {
+    use super::*;
+
+    pub fn file_subcommand() -> App<'static, 'static> {
+        SubCommand::with_name("file")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("file api")
+            .long_about("This command manipulate file api.")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(upload())
+            .subcommand(get())
+            .subcommand(ls())
+    }
+
+    fn channel() -> Arg<'static, 'static> {
+        Arg::with_name("channel")
+            .short("c")
+            .long("channel")
+            .help("channel name. the current channel if omitted")
+            .takes_value(true)
+            .value_name("CHANNEL")
+    }
+
+    fn upload() -> App<'static, 'static> {
+        SubCommand::with_name("upload")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("upload a file")
+            .long_about(
+                "upload a file to the channel and print its url, or post it with --message.",
+            )
+            .arg(channel())
+            .arg(
+                Arg::with_name("message")
+                    .short("m")
+                    .long("message")
+                    .help("post a message with the file")
+                    .takes_value(true)
+                    .value_name("TEXT"),
+            )
+            .arg(Arg::with_name("path").help("file to upload").required(true))
+    }
+
+    fn get() -> App<'static, 'static> {
+        SubCommand::with_name("get")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("download a file")
+            .arg(
+                Arg::with_name("out")
+                    .short("o")
+                    .long("out")
+                    .help("output path. the file name if omitted")
+                    .takes_value(true)
+                    .value_name("PATH"),
+            )
+            .arg(
+                Arg::with_name("file")
+                    .help("file id or link")
+                    .required(true),
+            )
+    }
+
+    fn ls() -> App<'static, 'static> {
+        SubCommand::with_name("ls")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("list files of a channel")
+            .arg(channel())
+            .arg(
+                Arg::with_name("limit")
+                    .short("n")
+                    .long("limit")
+                    .help("max number of files")
+                    .takes_value(true)
+                    .default_value("20"),
+            )
+            .arg(
+                Arg::with_name("mine")
+                    .long("mine")
+                    .help("only files uploaded by me"),
+            )
+    }
+}
+
+mod stamp {
+    use super::*;
+
+    pub fn stamp_subcommand() -> App<'static, 'static> {
+        SubCommand::with_name("stamp")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("stamp api")
+            .long_about("This command manipulate stamp api.")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(add())
+            .subcommand(rm())
+            .subcommand(ls())
+            .subcommand(find())
+            .subcommand(show())
+            .subcommand(upload())
+            .subcommand(rename())
+            .subcommand(delete())
+            .subcommand(palette())
+    }
+
+    fn message() -> Arg<'static, 'static> {
+        Arg::with_name("message")
+            .help("message id or link")
+            .required(true)
+    }
+
+    fn stamp() -> Arg<'static, 'static> {
+        Arg::with_name("stamp")
+            .help("stamp name. :name: is also accepted")
+            .required(true)
+    }
+
+    fn yes() -> Arg<'static, 'static> {
+        Arg::with_name("yes")
+            .short("y")
+            .long("yes")
+            .help("delete without confirmation")
+    }
+
+    fn add() -> App<'static, 'static> {
+        SubCommand::with_name("add")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("add a stamp to a message")
+            .arg(
+                Arg::with_name("count")
+                    .short("n")
+                    .long("count")
+                    .help("number of stamps")
+                    .takes_value(true)
+                    .default_value("1"),
+            )
+            .arg(message())
+            .arg(stamp())
+    }
+
+    fn rm() -> App<'static, 'static> {
+        SubCommand::with_name("rm")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("remove a stamp from a message")
+            .arg(message())
+            .arg(stamp())
+    }
+
+    fn ls() -> App<'static, 'static> {
+        SubCommand::with_name("ls")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("list stamps of a message, or all stamps")
+            .long_about(
+                "list stamps of a message with who stamped them. all stamps of the server are listed from the cache if no message is given.",
+            )
+            .arg(
+                Arg::with_name("refresh")
+                    .long("refresh")
+                    .help("update the cached stamps"),
+            )
+            .arg(
+                Arg::with_name("unicode")
+                    .short("u")
+                    .long("unicode")
+                    .help("include unicode emoji stamps"),
+            )
+            .arg(Arg::with_name("message").help("message id or link"))
+    }
+
+    fn find() -> App<'static, 'static> {
+        SubCommand::with_name("find")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("fuzzy search stamps")
+            .arg(
+                Arg::with_name("limit")
+                    .short("n")
+                    .long("limit")
+                    .help("max number of results")
+                    .takes_value(true)
+                    .default_value("20"),
+            )
+            .arg(Arg::with_name("query").help("search words").required(true))
+    }
+
+    fn show() -> App<'static, 'static> {
+        SubCommand::with_name("show")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("show details of a stamp")
+            .arg(stamp())
+    }
+
+    fn upload() -> App<'static, 'static> {
+        SubCommand::with_name("upload")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("create a stamp from a PNG or GIF image")
+            .arg(
+                Arg::with_name("file")
+                    .help("image file up to 1 MiB")
+                    .required(true),
+            )
+            .arg(Arg::with_name("name").help("stamp name. the file name if omitted"))
+    }
+
+    fn rename() -> App<'static, 'static> {
+        SubCommand::with_name("rename")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("rename your stamp")
+            .arg(stamp())
+            .arg(Arg::with_name("new_name").help("new name").required(true))
+    }
+
+    fn delete() -> App<'static, 'static> {
+        SubCommand::with_name("delete")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("delete your stamp")
+            .arg(yes())
+            .arg(stamp())
+    }
+
+    fn palette() -> App<'static, 'static> {
+        let palette = || {
+            Arg::with_name("palette")
+                .help("palette name")
+                .required(true)
+        };
+        let description = || {
+            Arg::with_name("description")
+                .short("d")
+                .long("description")
+                .help("description")
+                .takes_value(true)
+        };
+
+        SubCommand::with_name("palette")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("manage your stamp palettes")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("ls").about("list palettes"))
+            .subcommand(
+                SubCommand::with_name("show")
+                    .about("show stamps of a palette")
+                    .arg(palette()),
+            )
+            .subcommand(
+                SubCommand::with_name("create")
+                    .about("create a palette")
+                    .arg(description())
+                    .arg(palette())
+                    .arg(Arg::with_name("stamps").help("stamp names").multiple(true)),
+            )
+            .subcommand(
+                SubCommand::with_name("edit")
+                    .about("edit a palette")
+                    .arg(
+                        Arg::with_name("name")
+                            .long("name")
+                            .help("new name")
+                            .takes_value(true),
+                    )
+                    .arg(description())
+                    .arg(
+                        Arg::with_name("add")
+                            .short("a")
+                            .long("add")
+                            .help("stamps to add")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1),
+                    )
+                    .arg(
+                        Arg::with_name("remove")
+                            .short("r")
+                            .long("remove")
+                            .help("stamps to remove")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1),
+                    )
+                    .arg(palette()),
+            )
+            .subcommand(
+                SubCommand::with_name("rm")
+                    .about("delete a palette")
+                    .arg(yes())
+                    .arg(palette()),
+            )
+    }
+}
+
+mod user {
+    use super::*;
+
+    pub fn user_subcommand() -> App<'static, 'static> {
+        SubCommand::with_name("user")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("user api")
+            .long_about("This command manipulate user api.")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(ls())
+            .subcommand(show())
+            .subcommand(find())
+    }
+
+    fn ls() -> App<'static, 'static> {
+        SubCommand::with_name("ls")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("list users")
+            .arg(Arg::with_name("bots").long("bots").help("only bots"))
+            .arg(
+                Arg::with_name("active")
+                    .long("active")
+                    .help("only active users"),
+            )
+            .arg(
+                Arg::with_name("refresh")
+                    .long("refresh")
+                    .help("update the cached users"),
+            )
+    }
+
+    fn show() -> App<'static, 'static> {
+        SubCommand::with_name("show")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("show the profile of a user")
+            .arg(
+                Arg::with_name("user")
+                    .help("@name of the user")
+                    .required(true),
+            )
+    }
+
+    fn find() -> App<'static, 'static> {
+        SubCommand::with_name("find")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("fuzzy search users by name and display name")
+            .arg(
+                Arg::with_name("limit")
+                    .short("n")
+                    .long("limit")
+                    .help("max number of results")
+                    .takes_value(true)
+                    .default_value("20"),
+            )
+            .arg(Arg::with_name("query").help("search words").required(true))
+    }
+}
+
+mod group {
+    use super::*;
+
+    pub fn group_subcommand() -> App<'static, 'static> {
+        SubCommand::with_name("group")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("user group api")
+            .long_about("This command manipulate user group api.")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(ls())
+            .subcommand(show())
+            .subcommand(create())
+            .subcommand(rm())
+            .subcommand(member())
+            .subcommand(admin())
+            .subcommand(apply())
+    }
+
+    fn group() -> Arg<'static, 'static> {
+        Arg::with_name("group").help("group name").required(true)
+    }
+
+    fn users() -> Arg<'static, 'static> {
+        Arg::with_name("users")
+            .help("@names of users. read from stdin if omitted")
+            .multiple(true)
+    }
+
+    fn yes() -> Arg<'static, 'static> {
+        Arg::with_name("yes")
+            .short("y")
+            .long("yes")
+            .help("run without confirmation")
+    }
+
+    fn ls() -> App<'static, 'static> {
+        SubCommand::with_name("ls")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("list groups")
+    }
+
+    fn show() -> App<'static, 'static> {
+        SubCommand::with_name("show")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("show a group with its members")
+            .arg(group())
+    }
+
+    fn create() -> App<'static, 'static> {
+        SubCommand::with_name("create")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("create a group")
+            .arg(
+                Arg::with_name("description")
+                    .short("d")
+                    .long("description")
+                    .help("description")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("type")
+                    .short("t")
+                    .long("type")
+                    .help("group type, e.g. grade or team")
+                    .takes_value(true),
+            )
+            .arg(group())
+    }
+
+    fn rm() -> App<'static, 'static> {
+        SubCommand::with_name("rm")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("delete a group")
+            .arg(yes())
+            .arg(group())
+    }
+
+    fn member() -> App<'static, 'static> {
+        SubCommand::with_name("member")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("manage members of a group")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                SubCommand::with_name("add")
+                    .about("add members")
+                    .arg(
+                        Arg::with_name("role")
+                            .short("r")
+                            .long("role")
+                            .help("role of the members")
+                            .takes_value(true),
+                    )
+                    .arg(group())
+                    .arg(users()),
+            )
+            .subcommand(
+                SubCommand::with_name("rm")
+                    .about("remove members")
+                    .arg(group())
+                    .arg(users()),
+            )
+            .subcommand(
+                SubCommand::with_name("ls")
+                    .about("list members")
+                    .arg(group()),
+            )
+    }
+
+    fn admin() -> App<'static, 'static> {
+        SubCommand::with_name("admin")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("manage admins of a group")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                SubCommand::with_name("add")
+                    .about("add admins")
+                    .arg(group())
+                    .arg(users()),
+            )
+            .subcommand(
+                SubCommand::with_name("rm")
+                    .about("remove admins")
+                    .arg(group())
+                    .arg(users()),
+            )
+    }
+
+    fn apply() -> App<'static, 'static> {
+        SubCommand::with_name("apply")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("sync groups with a file")
+            .long_about(
+                "sync groups with a toml file. groups in the file are created if missing, and their members and admins are changed to match. member roles are not managed. groups not in the file are not touched.",
+            )
+            .arg(yes())
+            .arg(Arg::with_name("file").help("groups.toml").required(true))
+    }
+}
+
+mod me {
+    use super::*;
+
+    pub fn me_subcommand() -> App<'static, 'static> {
+        SubCommand::with_name("me")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("show or edit your profile")
+            .long_about(
+                "This command manipulate me api. your profile is shown without a subcommand.",
+            )
+            .subcommand(set())
+            .subcommand(icon())
+            .subcommand(tag())
+    }
+
+    fn set() -> App<'static, 'static> {
+        SubCommand::with_name("set")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("edit your profile")
+            .arg(
+                Arg::with_name("display-name")
+                    .long("display-name")
+                    .help("display name")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("bio")
+                    .long("bio")
+                    .help("bio")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("twitter")
+                    .long("twitter")
+                    .help("twitter id")
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("home-channel")
+                    .long("home-channel")
+                    .help("path of the home channel")
+                    .takes_value(true)
+                    .value_name("PATH"),
+            )
+            .group(
+                ArgGroup::with_name("fields")
+                    .args(&["display-name", "bio", "twitter", "home-channel"])
+                    .multiple(true)
+                    .required(true),
+            )
+    }
+
+    fn icon() -> App<'static, 'static> {
+        SubCommand::with_name("icon")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("change your icon")
+            .arg(Arg::with_name("image").help("image file").required(true))
+    }
+
+    fn tag() -> App<'static, 'static> {
+        let tag = || Arg::with_name("tag").help("tag").required(true);
+
+        SubCommand::with_name("tag")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("manage your tags")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("ls").about("list your tags"))
+            .subcommand(SubCommand::with_name("add").about("add a tag").arg(tag()))
+            .subcommand(SubCommand::with_name("rm").about("remove a tag").arg(tag()))
+            .subcommand(
+                SubCommand::with_name("lock")
+                    .about("lock a tag so others cannot remove it")
+                    .arg(tag()),
+            )
+            .subcommand(
+                SubCommand::with_name("unlock")
+                    .about("unlock a tag")
+                    .arg(tag()),
+            )
+    }
+}
+
+mod unread {
+    use super::*;
+
+    pub fn unread_subcommand() -> App<'static, 'static> {
+        SubCommand::with_name("unread")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("list unread channels")
+            .long_about(
+                "list unread channels in the order of the channel tree. @ marks channels with mentions.",
+            )
+            .subcommand(
+                SubCommand::with_name("read")
+                    .version(env!("CARGO_PKG_VERSION"))
+                    .author(env!("CARGO_PKG_AUTHORS"))
+                    .about("mark channels as read")
+                    .arg(
+                        Arg::with_name("all")
+                            .short("a")
+                            .long("all")
+                            .help("mark all channels as read")
+                            .conflicts_with_all(&["recursive", "channel_name"]),
+                    )
+                    .arg(
+                        Arg::with_name("recursive")
+                            .short("r")
+                            .long("recursive")
+                            .help("mark descendant channels too"),
+                    )
+                    .arg(
+                        Arg::with_name("channel_name")
+                            .help("channel name")
+                            .required_unless("all"),
+                    ),
+            )
+    }
+}
+
+mod pin {
+    use super::*;
+
+    pub fn pin_subcommand() -> App<'static, 'static> {
+        let message = || {
+            Arg::with_name("message")
+                .help("message id or link")
+                .required(true)
+        };
+
+        SubCommand::with_name("pin")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("pin api")
+            .long_about("This command manipulate pin api.")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                SubCommand::with_name("ls")
+                    .version(env!("CARGO_PKG_VERSION"))
+                    .author(env!("CARGO_PKG_AUTHORS"))
+                    .about("list pinned messages of a channel")
+                    .arg(
+                        Arg::with_name("channel_name")
+                            .help("channel name. the current channel if omitted"),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("add")
+                    .version(env!("CARGO_PKG_VERSION"))
+                    .author(env!("CARGO_PKG_AUTHORS"))
+                    .about("pin a message")
+                    .arg(message()),
+            )
+            .subcommand(
+                SubCommand::with_name("rm")
+                    .version(env!("CARGO_PKG_VERSION"))
+                    .author(env!("CARGO_PKG_AUTHORS"))
+                    .about("unpin a message")
+                    .arg(message()),
+            )
+    }
+}
+
+mod star {
+    use super::*;
+
+    pub fn star_subcommand() -> App<'static, 'static> {
+        let channel_names = || {
+            Arg::with_name("channel_names")
+                .help("channel names")
+                .multiple(true)
+                .required(true)
+        };
+
+        SubCommand::with_name("star")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("star api")
+            .long_about("This command manipulate star api.")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                SubCommand::with_name("ls")
+                    .version(env!("CARGO_PKG_VERSION"))
+                    .author(env!("CARGO_PKG_AUTHORS"))
+                    .about("list starred channels")
+                    .arg(Arg::with_name("channel_name").help("list only under this channel")),
+            )
+            .subcommand(
+                SubCommand::with_name("add")
+                    .version(env!("CARGO_PKG_VERSION"))
+                    .author(env!("CARGO_PKG_AUTHORS"))
+                    .about("star channels")
+                    .arg(channel_names()),
+            )
+            .subcommand(
+                SubCommand::with_name("rm")
+                    .version(env!("CARGO_PKG_VERSION"))
+                    .author(env!("CARGO_PKG_AUTHORS"))
+                    .about("unstar channels")
+                    .arg(channel_names()),
+            )
+    }
+}
+
+mod clip {
+    use super::*;
+
+    pub fn clip_subcommand() -> App<'static, 'static> {
+        SubCommand::with_name("clip")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("clip api")
+            .long_about("This command manipulate clip api.")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(ls())
+            .subcommand(create())
+            .subcommand(delete())
+            .subcommand(add())
+            .subcommand(rm())
+            .subcommand(show())
+    }
+
+    fn folder() -> Arg<'static, 'static> {
+        Arg::with_name("folder")
+            .help("clip folder name or id")
+            .required(true)
+    }
+
+    fn message() -> Arg<'static, 'static> {
+        Arg::with_name("message")
+            .help("message id or link")
+            .required(true)
+    }
+
+    fn ls() -> App<'static, 'static> {
+        SubCommand::with_name("ls")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("list clip folders")
+    }
+
+    fn create() -> App<'static, 'static> {
+        SubCommand::with_name("create")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("create a clip folder")
+            .arg(
+                Arg::with_name("description")
+                    .short("d")
+                    .long("description")
+                    .help("description")
+                    .takes_value(true),
+            )
+            .arg(Arg::with_name("folder").help("folder name").required(true))
+    }
+
+    fn delete() -> App<'static, 'static> {
+        SubCommand::with_name("delete")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("delete a clip folder")
+            .arg(
+                Arg::with_name("yes")
+                    .short("y")
+                    .long("yes")
+                    .help("delete without confirmation"),
+            )
+            .arg(folder())
+    }
+
+    fn add() -> App<'static, 'static> {
+        SubCommand::with_name("add")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("clip a message")
+            .arg(folder())
+            .arg(message())
+    }
+
+    fn rm() -> App<'static, 'static> {
+        SubCommand::with_name("rm")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("remove a message from a clip folder")
+            .arg(folder())
+            .arg(message())
+    }
+
+    fn show() -> App<'static, 'static> {
+        SubCommand::with_name("show")
+            .version(env!("CARGO_PKG_VERSION"))
+            .author(env!("CARGO_PKG_AUTHORS"))
+            .about("list clipped messages, the most recently clipped first")
+            .arg(
+                Arg::with_name("limit")
+                    .short("n")
+                    .long("limit")
+                    .help("max number of messages")
+                    .takes_value(true)
+                    .default_value("20"),
+            )
+            .arg(folder())
     }
 }
@@ -0,0 +1,225 @@
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{clip_api, configuration::Configuration},
+    models::{ClipFolder, PostClipFolderMessageRequest, PostClipFolderRequest},
+};
+use serde::Serialize;
+
+use super::{
+    channel, link,
+    msg::{self, format_time, MessageView},
+    output::{self, OutputFormat, Render},
+    prompt,
+};
+
+/// The maximum `limit` of `get_clips`.
+const PAGE_SIZE: usize = 50;
+
+#[derive(Debug, Serialize)]
+pub struct FolderEntry {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub created_at: String,
+}
+
+impl From<ClipFolder> for FolderEntry {
+    fn from(f: ClipFolder) -> Self {
+        Self {
+            id: f.id,
+            name: f.name,
+            description: f.description,
+            created_at: f.created_at,
+        }
+    }
+}
+
+impl Render for FolderEntry {
+    fn header() -> &'static [&'static str] {
+        &["id", "name", "description", "created_at"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.description.clone(),
+            self.created_at.clone(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!("{}  {}", self.name, self.description)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClipEntry {
+    pub channel: String,
+    pub clipped_at: String,
+    #[serde(flatten)]
+    pub message: MessageView,
+}
+
+impl Render for ClipEntry {
+    fn header() -> &'static [&'static str] {
+        &[
+            "id",
+            "channel",
+            "user",
+            "created_at",
+            "clipped_at",
+            "content",
+        ]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.message.id.clone(),
+            self.channel.clone(),
+            self.message.user.clone(),
+            self.message.created_at.clone(),
+            self.clipped_at.clone(),
+            self.message.content.clone(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!(
+            "{}  {}\n  (clipped {})",
+            self.channel,
+            self.message.text(),
+            format_time(&self.clipped_at)
+        )
+    }
+}
+
+pub async fn clip(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("ls", Some(m)) => ls(conf, m).await,
+        ("create", Some(m)) => create(conf, m).await,
+        ("delete", Some(m)) => delete(conf, m).await,
+        ("add", Some(m)) => add(conf, m).await,
+        ("rm", Some(m)) => rm(conf, m).await,
+        ("show", Some(m)) => show(conf, m).await,
+        (x, _) => bail!("unknown clip command '{}'", x),
+    }
+}
+
+/// My clip folder named `name`. An id is also accepted.
+async fn find(conf: &Configuration, name: &str) -> Result<ClipFolder> {
+    clip_api::get_clip_folders(conf)
+        .await
+        .with_context(|| "cannot get clip folders")?
+        .into_iter()
+        .find(|f| f.name == name || f.id == name)
+        .with_context(|| format!("clip folder not found: {}", name))
+}
+
+async fn ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let mut folders = clip_api::get_clip_folders(conf)
+        .await
+        .with_context(|| "cannot get clip folders")?;
+    folders.sort_by(|a, b| a.name.cmp(&b.name));
+    let folders: Vec<FolderEntry> = folders.into_iter().map(FolderEntry::from).collect();
+    output::render(format, &folders)
+}
+
+async fn create(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let name = matches.value_of("folder").unwrap();
+    let description = matches.value_of("description").unwrap_or_default();
+    let req = PostClipFolderRequest::new(name.to_owned(), description.to_owned());
+    let folder = clip_api::create_clip_folder(conf, Some(req))
+        .await
+        .with_context(|| format!("cannot create {}", name))?;
+    println!("created {} ({})", folder.name, folder.id);
+
+    Ok(())
+}
+
+async fn delete(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let folder = find(conf, matches.value_of("folder").unwrap()).await?;
+    if !matches.is_present("yes")
+        && !prompt::confirm(&format!("delete clip folder {}?", folder.name))?
+    {
+        println!("canceled");
+        return Ok(());
+    }
+
+    clip_api::delete_clip_folder(conf, &folder.id)
+        .await
+        .with_context(|| format!("cannot delete {}", folder.name))?;
+    println!("deleted {}", folder.name);
+
+    Ok(())
+}
+
+async fn add(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let folder = find(conf, matches.value_of("folder").unwrap()).await?;
+    let id = link::message_id(matches.value_of("message").unwrap())?;
+    clip_api::clip_message(
+        conf,
+        &folder.id,
+        Some(PostClipFolderMessageRequest::new(id.clone())),
+    )
+    .await
+    .with_context(|| format!("cannot clip {} to {}", id, folder.name))?;
+    Ok(())
+}
+
+async fn rm(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let folder = find(conf, matches.value_of("folder").unwrap()).await?;
+    let id = link::message_id(matches.value_of("message").unwrap())?;
+    clip_api::unclip_message(conf, &folder.id, &id)
+        .await
+        .with_context(|| format!("cannot remove {} from {}", id, folder.name))?;
+    Ok(())
+}
+
+/// Clipped messages, the most recently clipped first.
+async fn show(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let folder = find(conf, matches.value_of("folder").unwrap()).await?;
+    let limit: usize = matches
+        .value_of("limit")
+        .unwrap()
+        .parse()
+        .with_context(|| "limit must be a number")?;
+
+    let mut clips = Vec::new();
+    while clips.len() < limit {
+        let n = (limit - clips.len()).min(PAGE_SIZE);
+        let page = clip_api::get_clips(
+            conf,
+            &folder.id,
+            Some(n as i32),
+            Some(clips.len() as i32),
+            Some("desc"),
+        )
+        .await
+        .with_context(|| format!("cannot get clips of {}", folder.name))?;
+        let done = page.len() < n;
+        clips.extend(page);
+        if done {
+            break;
+        }
+    }
+
+    let users = msg::get_users(conf).await?;
+    let tree = channel::get_channel_tree(conf).await?;
+    let paths = tree.paths();
+    let entries: Vec<ClipEntry> = clips
+        .into_iter()
+        .map(|c| ClipEntry {
+            channel: paths
+                .get(&c.message.channel_id)
+                .cloned()
+                .unwrap_or_default(),
+            clipped_at: c.clipped_at,
+            message: MessageView::new(c.message, &users, &paths),
+        })
+        .collect();
+    output::render(format, &entries)
+}
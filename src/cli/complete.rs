@@ -0,0 +1,78 @@
+use anyhow::Result;
+use clap::{ArgMatches, Shell};
+
+use super::channel;
+
+/// Prints channel paths completing `word`, one per line.
+/// Only the cached channels are used, so this never asks for authentication.
+pub fn complete(matches: &ArgMatches<'_>) -> Result<()> {
+    let word = matches.value_of("word").unwrap_or("");
+    if let Ok(mut tree) = channel::get_cached_channel_tree() {
+        for path in channel::complete_path(&mut tree, word) {
+            println!("{}", path);
+        }
+    }
+    Ok(())
+}
+
+/// Adds channel path completion to the script generated by clap.
+/// Words starting with `/` or `.` are completed as channel paths.
+pub fn with_channel_completion(shell: Shell, bin: &str, script: String) -> String {
+    match shell {
+        Shell::Bash => format!(
+            r#"{script}
+_{bin}_channels() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [[ "$cur" == /* || "$cur" == .* ]]; then
+        local IFS=$'\n'
+        COMPREPLY=( $({bin} __complete channel "$cur" 2>/dev/null) )
+        if [[ ${{#COMPREPLY[@]}} -gt 0 ]]; then
+            compopt -o nospace
+            return 0
+        fi
+    fi
+    _{bin} "$@"
+}}
+
+complete -F _{bin}_channels -o bashdefault -o default {bin}
+"#,
+            script = script,
+            bin = bin
+        ),
+        Shell::Zsh => {
+            let call = format!("_{} \"$@\"", bin);
+            let dispatch = format!(
+                r#"_{bin}_channels() {{
+    if [[ "$PREFIX" == /* || "$PREFIX" == .* ]]; then
+        local -a channels
+        channels=(${{(f)"$({bin} __complete channel "$PREFIX" 2>/dev/null)"}})
+        if (( ${{#channels}} )); then
+            compadd -S '' -- $channels
+            return
+        fi
+    fi
+    _{bin} "$@"
+}}
+
+_{bin}_channels "$@"
+"#,
+                bin = bin
+            );
+            match script.rfind(&call) {
+                Some(i) => format!("{}{}{}", &script[..i], dispatch, &script[i + call.len()..]),
+                None => format!("{}\n{}", script, dispatch),
+            }
+        }
+        Shell::Fish => format!(
+            r#"{script}
+function __fish_{bin}_channel_token
+    string match -qr '^[./]' -- (commandline -ct)
+end
+complete -c {bin} -n '__fish_{bin}_channel_token' -f -a '({bin} __complete channel (commandline -ct))'
+"#,
+            script = script,
+            bin = bin
+        ),
+        _ => script,
+    }
+}
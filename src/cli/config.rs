@@ -0,0 +1,37 @@
+use serde::Serialize;
+
+use super::output::Render;
+use crate::config::Data;
+
+/// What `--show-config` prints.
+#[derive(Debug, Serialize)]
+pub struct ConfigEntry {
+    pub server_url: String,
+    pub client_id: String,
+}
+
+impl From<&Data> for ConfigEntry {
+    fn from(data: &Data) -> Self {
+        Self {
+            server_url: data.server_url().to_owned(),
+            client_id: data.client_id().to_owned(),
+        }
+    }
+}
+
+impl Render for ConfigEntry {
+    fn header() -> &'static [&'static str] {
+        &["server_url", "client_id"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![self.server_url.clone(), self.client_id.clone()]
+    }
+
+    fn text(&self) -> String {
+        format!(
+            "server_url: {}\nclient_id: {}",
+            self.server_url, self.client_id
+        )
+    }
+}
@@ -0,0 +1,375 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, DirBuilder, OpenOptions},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use indicatif::ProgressBar;
+use rust_traq::{
+    apis::{configuration::Configuration, file_api, message_api},
+    models::{Message, User},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    channel::{self, ChannelEntry},
+    file, link,
+    msg::{self, MessageView},
+};
+
+/// The maximum `limit` of `get_messages`.
+const PAGE_SIZE: i32 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Jsonl,
+    Md,
+    Html,
+}
+
+impl ExportFormat {
+    pub const VALUES: &'static [&'static str] = &["jsonl", "md", "html"];
+
+    /// One checkpoint file per format, as each format has its own output files.
+    fn checkpoint_file(self) -> String {
+        format!("checkpoint.{}.json", self.extension())
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Md => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "jsonl" => Ok(Self::Jsonl),
+            "md" => Ok(Self::Md),
+            "html" => Ok(Self::Html),
+            x => bail!("unknown export format '{}'", x),
+        }
+    }
+}
+
+/// The last exported messages of a channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub last_created_at: String,
+    /// every exported message at `last_created_at`, which the next page fetches again
+    pub ids: Vec<String>,
+    /// size of the export file when the checkpoint was saved
+    pub len: u64,
+}
+
+impl Checkpoint {
+    fn new(m: &Message) -> Self {
+        Self {
+            last_created_at: m.created_at.clone(),
+            ids: vec![m.id.clone()],
+            len: 0,
+        }
+    }
+
+    fn contains(&self, m: &Message) -> bool {
+        m.created_at == self.last_created_at && self.ids.contains(&m.id)
+    }
+
+    /// Moves the checkpoint to `m`, which must not be older than the current one.
+    fn advance(&mut self, m: &Message) {
+        if m.created_at != self.last_created_at {
+            self.ids.clear();
+            self.last_created_at = m.created_at.clone();
+        }
+        self.ids.push(m.id.clone());
+    }
+}
+
+/// A line of the `jsonl` format.
+#[derive(Serialize)]
+struct Record<'a> {
+    #[serde(flatten)]
+    message: &'a MessageView,
+    /// `content` with embeds expanded
+    text: &'a str,
+    files: &'a [String],
+}
+
+struct Exporter<'a> {
+    conf: &'a Configuration,
+    format: ExportFormat,
+    out: PathBuf,
+    attachments: bool,
+    users: BTreeMap<String, User>,
+    paths: BTreeMap<String, String>,
+    /// checkpoints by channel id
+    checkpoints: BTreeMap<String, Checkpoint>,
+}
+
+pub async fn export(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let name = matches.value_of("channel_name").unwrap();
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let entries = tree
+        .entries(Path::new(name), matches.is_present("recursive"))
+        .with_context(|| format!("channel name not found: {}", name))?;
+    if entries.is_empty() {
+        bail!("root is not a channel. use --recursive to export all channels");
+    }
+
+    let out = PathBuf::from(matches.value_of("out").unwrap());
+    DirBuilder::new()
+        .recursive(true)
+        .create(&out)
+        .with_context(|| format!("cannot create {}", out.display()))?;
+    let format: ExportFormat = matches.value_of("format").unwrap().parse()?;
+    let checkpoints = match fs::read_to_string(out.join(format.checkpoint_file())) {
+        Ok(s) => serde_json::from_str(&s).with_context(|| "cannot parse checkpoint")?,
+        Err(_) => BTreeMap::new(),
+    };
+
+    let mut exporter = Exporter {
+        conf,
+        format,
+        out,
+        attachments: matches.is_present("attachments"),
+        users: msg::get_users(conf).await?,
+        paths: tree.paths(),
+        checkpoints,
+    };
+    for entry in entries.iter() {
+        let n = exporter.export_channel(entry).await?;
+        eprintln!("{}: {} messages", entry.path, n);
+    }
+
+    Ok(())
+}
+
+impl Exporter<'_> {
+    /// Appends messages newer than the checkpoint and returns how many were written.
+    async fn export_channel(&mut self, entry: &ChannelEntry) -> Result<usize> {
+        let file = self.out.join(format!(
+            "{}.{}",
+            entry.path.trim_start_matches('/'),
+            self.format.extension()
+        ));
+        if let Some(dir) = file.parent() {
+            DirBuilder::new().recursive(true).create(dir)?;
+        }
+        let mut cp = self.checkpoints.get(&entry.id).cloned();
+        let f = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file)
+            .with_context(|| format!("cannot open {}", file.display()))?;
+        // anything written after the checkpoint was saved is fetched and written again
+        f.set_len(cp.as_ref().map_or(0, |cp| cp.len))
+            .with_context(|| format!("cannot truncate {}", file.display()))?;
+        let mut w = BufWriter::new(f);
+        if cp.is_none() {
+            self.write_header(&mut w, &entry.path)?;
+        }
+
+        let mut offset = 0;
+        let mut count = 0;
+        loop {
+            // messages at the checkpoint time are fetched again and skipped by id,
+            // so ones sharing a timestamp across pages are not lost
+            let page = message_api::get_messages(
+                self.conf,
+                &entry.id,
+                Some(PAGE_SIZE),
+                Some(offset),
+                cp.as_ref().map(|cp| cp.last_created_at.clone()),
+                None,
+                Some(true),
+                Some("asc"),
+            )
+            .await
+            .with_context(|| format!("cannot get messages of {}", entry.path))?;
+            let done = page.len() < PAGE_SIZE as usize;
+            let new: Vec<Message> = page
+                .into_iter()
+                .filter(|m| !matches!(&cp, Some(cp) if cp.contains(m)))
+                .collect();
+            if new.is_empty() {
+                if done {
+                    break;
+                }
+                // a whole page at one timestamp was exported before
+                offset += PAGE_SIZE;
+                continue;
+            }
+            offset = 0;
+
+            // the page is written before its checkpoint, and the file is cut back to the
+            // checkpoint on resume, so a failure in between does not duplicate the page
+            let mut buf = Vec::new();
+            let mut next = cp.clone();
+            for m in new.iter() {
+                match next.as_mut() {
+                    Some(c) => c.advance(m),
+                    None => next = Some(Checkpoint::new(m)),
+                }
+            }
+            let mut next = next.unwrap();
+            count += new.len();
+            for m in new {
+                self.write_message(&mut buf, m, &file).await?;
+            }
+            w.write_all(&buf)?;
+            w.flush()?;
+            next.len = w.get_ref().metadata()?.len();
+            self.checkpoints.insert(entry.id.clone(), next.clone());
+            self.save_checkpoints()?;
+            cp = Some(next);
+
+            if done {
+                break;
+            }
+        }
+
+        Ok(count)
+    }
+
+    fn save_checkpoints(&self) -> Result<()> {
+        let path = self.out.join(self.format.checkpoint_file());
+        let content = serde_json::to_string_pretty(&self.checkpoints)?;
+        // replaced at once so a failure never leaves a broken checkpoint
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, content).with_context(|| format!("cannot write {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("cannot write {}", path.display()))
+    }
+
+    fn write_header<W: Write>(&self, w: &mut W, path: &str) -> Result<()> {
+        match self.format {
+            ExportFormat::Jsonl => {}
+            ExportFormat::Md => writeln!(w, "# {}\n", path)?,
+            // </body> and </html> may be omitted, so messages can be appended later
+            ExportFormat::Html => write!(
+                w,
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>article {{ margin: 1em 0; }} pre {{ white-space: pre-wrap; }}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+                escape_html(path),
+                escape_html(path)
+            )?,
+        }
+        Ok(())
+    }
+
+    async fn write_message<W: Write>(&self, w: &mut W, m: Message, file: &Path) -> Result<()> {
+        let files = link::file_ids(&m.content);
+        if self.attachments {
+            let dir = file.with_extension("files");
+            for id in files.iter() {
+                self.download_attachment(id, &dir).await?;
+            }
+        }
+
+        let view = MessageView::new(m, &self.users, &self.paths);
+        let time = msg::format_time(&view.created_at);
+        match self.format {
+            ExportFormat::Jsonl => {
+                let record = Record {
+                    message: &view,
+                    text: &view.text,
+                    files: &files,
+                };
+                serde_json::to_writer(&mut *w, &record)?;
+                writeln!(w)?;
+            }
+            ExportFormat::Md => {
+                writeln!(w, "### {} (@{}) {}\n", view.display_name, view.user, time)?;
+                writeln!(w, "{}\n", view.text)?;
+            }
+            ExportFormat::Html => writeln!(
+                w,
+                "<article id=\"{}\">\n<header><b>{}</b> @{} <time datetime=\"{}\">{}</time></header>\n<pre>{}</pre>\n</article>",
+                view.id,
+                escape_html(&view.display_name),
+                escape_html(&view.user),
+                view.created_at,
+                time,
+                escape_html(&view.text)
+            )?,
+        }
+        Ok(())
+    }
+
+    /// Saves a file as `<dir>/<id>-<name>` unless it was downloaded before.
+    async fn download_attachment(&self, id: &str, dir: &Path) -> Result<()> {
+        let meta = match file_api::get_file_meta(self.conf, id).await {
+            Ok(meta) => meta,
+            // deleted or not visible
+            Err(_) => return Ok(()),
+        };
+        let path = dir.join(format!("{}-{}", id, file::local_name(&meta.name, id)));
+        if path.exists() {
+            return Ok(());
+        }
+        DirBuilder::new().recursive(true).create(dir)?;
+        file::download(self.conf, id, &path, &ProgressBar::hidden()).await
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, created_at: &str) -> Message {
+        Message::new(
+            id.to_owned(),
+            String::new(),
+            String::new(),
+            String::new(),
+            created_at.to_owned(),
+            created_at.to_owned(),
+            false,
+            Vec::new(),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let (a, b, c) = (message("a", "t1"), message("b", "t1"), message("c", "t2"));
+        let mut cp = Checkpoint::new(&a);
+        cp.advance(&b);
+        assert!(cp.contains(&a) && cp.contains(&b));
+        assert!(!cp.contains(&message("x", "t1")));
+
+        cp.advance(&c);
+        assert_eq!(cp.ids, vec!["c"]);
+        assert!(!cp.contains(&a) && cp.contains(&c));
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html(r#"<a href="x">'&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use rust_traq::{
+    apis::{configuration::Configuration, file_api, message_api},
+    models::{FileInfo, PostMessageRequest},
+};
+use serde::Serialize;
+
+use super::{
+    channel, link,
+    msg::{self, format_time},
+    output::{self, OutputFormat, Render},
+};
+
+#[derive(Debug, Serialize)]
+pub struct FileEntry {
+    pub id: String,
+    pub name: String,
+    pub mime: String,
+    pub size: i64,
+    pub created_at: String,
+    pub uploader_id: Option<String>,
+}
+
+impl From<FileInfo> for FileEntry {
+    fn from(f: FileInfo) -> Self {
+        Self {
+            id: f.id,
+            name: f.name,
+            mime: f.mime,
+            size: f.size,
+            created_at: f.created_at,
+            uploader_id: f.uploader_id,
+        }
+    }
+}
+
+impl Render for FileEntry {
+    fn header() -> &'static [&'static str] {
+        &["id", "name", "mime", "size", "created_at", "uploader_id"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.mime.clone(),
+            self.size.to_string(),
+            self.created_at.clone(),
+            self.uploader_id.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!(
+            "{}  {:>10}  {}  {}",
+            format_time(&self.created_at),
+            HumanBytes(self.size.max(0) as u64).to_string(),
+            self.id,
+            self.name
+        )
+    }
+}
+
+pub async fn file(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("upload", Some(m)) => upload(conf, m).await,
+        ("get", Some(m)) => get(conf, m).await,
+        ("ls", Some(m)) => ls(conf, m).await,
+        (x, _) => bail!("unknown file command '{}'", x),
+    }
+}
+
+/// `https://q.trap.jp/api/v3` -> `https://q.trap.jp/files/<id>`
+fn file_url(base_path: &str, id: &str) -> String {
+    let host = base_path.trim_end_matches('/').trim_end_matches("/api/v3");
+    format!("{}/files/{}", host, id)
+}
+
+async fn upload(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let path = PathBuf::from(matches.value_of("path").unwrap());
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let channel_id = msg::channel_id(&mut tree, matches.value_of("channel"))?;
+
+    let info = file_api::post_file(conf, path.clone(), &channel_id)
+        .await
+        .with_context(|| format!("cannot upload {}", path.display()))?;
+    let url = file_url(&conf.base_path, &info.id);
+
+    match matches.value_of("message") {
+        Some(text) => {
+            let content = format!("{}\n{}", text, url);
+            let msg = message_api::post_message(
+                conf,
+                &channel_id,
+                Some(PostMessageRequest::new(content)),
+            )
+            .await
+            .with_context(|| "cannot post message")?;
+            println!("{}", msg.id);
+        }
+        None => println!("{}", url),
+    }
+
+    Ok(())
+}
+
+async fn get(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let id = link::file_id(matches.value_of("file").unwrap())?;
+    let meta = file_api::get_file_meta(conf, &id)
+        .await
+        .with_context(|| format!("cannot get file {}", id))?;
+    let out = match matches.value_of("out") {
+        Some(out) => PathBuf::from(out),
+        None => PathBuf::from(local_name(&meta.name, &id)),
+    };
+
+    let pb = ProgressBar::new(meta.size.max(0) as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{bar:40} {bytes}/{total_bytes} {bytes_per_sec} {eta}"),
+    );
+    download(conf, &id, &out, &pb).await?;
+    pb.finish_and_clear();
+    println!("saved {}", out.display());
+
+    Ok(())
+}
+
+async fn ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let limit: i32 = matches
+        .value_of("limit")
+        .unwrap()
+        .parse()
+        .with_context(|| "limit must be a number")?;
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let channel_id = msg::channel_id(&mut tree, matches.value_of("channel"))?;
+
+    let files = file_api::get_files(
+        conf,
+        Some(&channel_id),
+        Some(limit),
+        None,
+        None,
+        None,
+        None,
+        Some("desc"),
+        Some(matches.is_present("mine")),
+    )
+    .await
+    .with_context(|| "cannot get files")?;
+    let files: Vec<FileEntry> = files.into_iter().map(FileEntry::from).collect();
+    output::render(format, &files)
+}
+
+/// A name from the server usable as a file name in the current directory.
+/// Path separators are replaced, and a name that is empty, `.` or `..` falls back to `id`.
+pub(crate) fn local_name(name: &str, id: &str) -> String {
+    let name = name.replace(&['/', '\\'][..], "_");
+    match name.as_str() {
+        "" | "." | ".." => id.to_owned(),
+        _ => name,
+    }
+}
+
+/// Streams the file to `path`, advancing `pb` by the bytes written.
+/// The data goes to `<path>.part` first, which is removed if the download fails.
+pub(crate) async fn download(
+    conf: &Configuration,
+    id: &str,
+    path: &Path,
+    pb: &ProgressBar,
+) -> Result<()> {
+    let token = conf.oauth_access_token.as_deref().unwrap_or_default();
+    let mut res = conf
+        .client
+        .get(&format!("{}/files/{}", conf.base_path, id))
+        .bearer_auth(token)
+        .send()
+        .await?
+        .error_for_status()
+        .with_context(|| format!("cannot download {}", id))?;
+
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    let mut f = BufWriter::new(
+        File::create(&part).with_context(|| format!("cannot create {}", path.display()))?,
+    );
+    let written: Result<()> = async {
+        while let Some(chunk) = res.chunk().await? {
+            f.write_all(&chunk)?;
+            pb.inc(chunk.len() as u64);
+        }
+        f.flush()?;
+        Ok(())
+    }
+    .await;
+    if let Err(e) = written {
+        drop(f);
+        let _ = fs::remove_file(&part);
+        return Err(e.context(format!("cannot download {}", id)));
+    }
+    fs::rename(&part, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_name() {
+        assert_eq!(local_name("a.png", "id"), "a.png");
+        assert_eq!(local_name("../a/b\\c", "id"), ".._a_b_c");
+        assert_eq!(local_name("..", "id"), "id");
+        assert_eq!(local_name(".", "id"), "id");
+        assert_eq!(local_name("", "id"), "id");
+    }
+
+    #[test]
+    fn test_file_url() {
+        assert_eq!(
+            file_url("https://q.trap.jp/api/v3", "abc"),
+            "https://q.trap.jp/files/abc"
+        );
+    }
+}
@@ -0,0 +1,325 @@
+mod apply;
+
+use std::io::{self, Read};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, group_api},
+    models::{PostUserGroupAdminRequest, PostUserGroupRequest, UserGroup, UserGroupMember},
+};
+use serde::Serialize;
+
+use super::{
+    msg,
+    output::{self, OutputFormat, Render},
+    prompt, user,
+};
+
+#[derive(Debug, Serialize)]
+pub struct GroupEntry {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub description: String,
+    pub members: usize,
+    /// `@name` of admins
+    pub admins: Vec<String>,
+}
+
+impl Render for GroupEntry {
+    fn header() -> &'static [&'static str] {
+        &["id", "name", "type", "description", "members", "admins"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.kind.clone(),
+            self.description.clone(),
+            self.members.to_string(),
+            self.admins.join(" "),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!(
+            "{} ({} members)  {}",
+            self.name, self.members, self.description
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemberEntry {
+    pub id: String,
+    pub name: String,
+    pub role: String,
+    pub admin: bool,
+}
+
+impl Render for MemberEntry {
+    fn header() -> &'static [&'static str] {
+        &["id", "name", "role", "admin"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.role.clone(),
+            self.admin.to_string(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        let mut text = format!("@{}", self.name);
+        if !self.role.is_empty() {
+            text.push_str(&format!("  {}", self.role));
+        }
+        if self.admin {
+            text.push_str("  [admin]");
+        }
+        text
+    }
+}
+
+pub async fn group(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("ls", Some(m)) => ls(conf, m).await,
+        ("show", Some(m)) => show(conf, m).await,
+        ("create", Some(m)) => create(conf, m).await,
+        ("rm", Some(m)) => rm(conf, m).await,
+        ("member", Some(m)) => match m.subcommand() {
+            ("add", Some(m)) => member_add(conf, m).await,
+            ("rm", Some(m)) => member_rm(conf, m).await,
+            ("ls", Some(m)) => member_ls(conf, m).await,
+            (x, _) => bail!("unknown member command '{}'", x),
+        },
+        ("admin", Some(m)) => match m.subcommand() {
+            ("add", Some(m)) => admin_add(conf, m).await,
+            ("rm", Some(m)) => admin_rm(conf, m).await,
+            (x, _) => bail!("unknown admin command '{}'", x),
+        },
+        ("apply", Some(m)) => apply::apply(conf, m).await,
+        (x, _) => bail!("unknown group command '{}'", x),
+    }
+}
+
+pub(crate) async fn get_groups(conf: &Configuration) -> Result<Vec<UserGroup>> {
+    group_api::get_user_groups(conf)
+        .await
+        .with_context(|| "cannot get groups")
+}
+
+/// The group named `name`.
+async fn find(conf: &Configuration, name: &str) -> Result<UserGroup> {
+    get_groups(conf)
+        .await?
+        .into_iter()
+        .find(|g| g.name == name)
+        .with_context(|| format!("group not found: {}", name))
+}
+
+/// `@name`s given as arguments, or read from stdin if there are none.
+fn user_names(matches: &ArgMatches<'_>) -> Result<Vec<String>> {
+    match matches.values_of("users") {
+        Some(names) => Ok(names.map(|v| v.to_owned()).collect()),
+        None => {
+            let mut names = String::new();
+            io::stdin().read_to_string(&mut names)?;
+            Ok(names.split_whitespace().map(|v| v.to_owned()).collect())
+        }
+    }
+}
+
+async fn group_entries(conf: &Configuration, groups: Vec<UserGroup>) -> Result<Vec<GroupEntry>> {
+    let users = msg::get_users(conf).await?;
+    let name = |id: &String| {
+        users
+            .get(id)
+            .map_or_else(|| id.clone(), |u| format!("@{}", u.name))
+    };
+    let entries = groups
+        .into_iter()
+        .map(|g| GroupEntry {
+            members: g.members.len(),
+            admins: g.admins.iter().map(name).collect(),
+            id: g.id,
+            name: g.name,
+            kind: g._type,
+            description: g.description,
+        })
+        .collect();
+    Ok(entries)
+}
+
+async fn member_entries(conf: &Configuration, group: &UserGroup) -> Result<Vec<MemberEntry>> {
+    let users = msg::get_users(conf).await?;
+    let mut entries: Vec<MemberEntry> = group
+        .members
+        .iter()
+        .map(|m| MemberEntry {
+            id: m.id.clone(),
+            name: users
+                .get(&m.id)
+                .map_or_else(|| m.id.clone(), |u| u.name.clone()),
+            role: m.role.clone(),
+            admin: group.admins.contains(&m.id),
+        })
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+async fn ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let mut groups = get_groups(conf).await?;
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+    output::render(format, &group_entries(conf, groups).await?)
+}
+
+async fn show(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let group = find(conf, matches.value_of("group").unwrap()).await?;
+    let members = member_entries(conf, &group).await?;
+    let entries = group_entries(conf, vec![group]).await?;
+    if !format.is_text() {
+        return output::render(format, &entries);
+    }
+
+    for e in entries.iter() {
+        println!("{}", e.name);
+        println!("id:          {}", e.id);
+        println!("type:        {}", e.kind);
+        println!("description: {}", e.description);
+        println!("admins:      {}", e.admins.join(" "));
+        println!("\n{} members", e.members);
+    }
+    for m in members.iter() {
+        println!("  {}", m.text());
+    }
+    Ok(())
+}
+
+async fn create(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let name = matches.value_of("group").unwrap();
+    let req = PostUserGroupRequest::new(
+        name.to_owned(),
+        matches
+            .value_of("description")
+            .unwrap_or_default()
+            .to_owned(),
+        matches.value_of("type").unwrap_or_default().to_owned(),
+    );
+    let group = group_api::create_user_group(conf, Some(req))
+        .await
+        .with_context(|| format!("cannot create {}", name))?;
+    println!("created {} ({})", group.name, group.id);
+
+    Ok(())
+}
+
+async fn rm(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let group = find(conf, matches.value_of("group").unwrap()).await?;
+    if !matches.is_present("yes")
+        && !prompt::confirm(&format!(
+            "delete {} with {} members?",
+            group.name,
+            group.members.len()
+        ))?
+    {
+        println!("canceled");
+        return Ok(());
+    }
+
+    group_api::delete_user_group(conf, &group.id)
+        .await
+        .with_context(|| format!("cannot delete {}", group.name))?;
+    println!("deleted {}", group.name);
+
+    Ok(())
+}
+
+async fn member_add(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let group = find(conf, matches.value_of("group").unwrap()).await?;
+    let role = matches.value_of("role").unwrap_or_default();
+    let names = user_names(matches)?;
+
+    let mut added = 0;
+    for name in names.iter() {
+        let u = user::resolve(conf, name).await?;
+        if group.members.iter().any(|m| m.id == u.id && m.role == role) {
+            continue;
+        }
+        let member = UserGroupMember::new(u.id.clone(), role.to_owned());
+        group_api::add_user_group_member(conf, &group.id, Some(member))
+            .await
+            .with_context(|| format!("cannot add @{} to {}", u.name, group.name))?;
+        added += 1;
+    }
+    println!("added {} members to {}", added, group.name);
+
+    Ok(())
+}
+
+async fn member_rm(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let group = find(conf, matches.value_of("group").unwrap()).await?;
+    let names = user_names(matches)?;
+
+    let mut removed = 0;
+    for name in names.iter() {
+        let u = user::resolve(conf, name).await?;
+        if !group.members.iter().any(|m| m.id == u.id) {
+            eprintln!("@{} is not a member of {}", u.name, group.name);
+            continue;
+        }
+        group_api::remove_user_group_member(conf, &group.id, &u.id)
+            .await
+            .with_context(|| format!("cannot remove @{} from {}", u.name, group.name))?;
+        removed += 1;
+    }
+    println!("removed {} members from {}", removed, group.name);
+
+    Ok(())
+}
+
+async fn member_ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let group = find(conf, matches.value_of("group").unwrap()).await?;
+    output::render(format, &member_entries(conf, &group).await?)
+}
+
+async fn admin_add(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let group = find(conf, matches.value_of("group").unwrap()).await?;
+    for name in user_names(matches)?.iter() {
+        let u = user::resolve(conf, name).await?;
+        if group.admins.contains(&u.id) {
+            continue;
+        }
+        group_api::add_user_group_admin(
+            conf,
+            &group.id,
+            Some(PostUserGroupAdminRequest::new(u.id.clone())),
+        )
+        .await
+        .with_context(|| format!("cannot make @{} an admin of {}", u.name, group.name))?;
+    }
+    Ok(())
+}
+
+async fn admin_rm(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let group = find(conf, matches.value_of("group").unwrap()).await?;
+    for name in user_names(matches)?.iter() {
+        let u = user::resolve(conf, name).await?;
+        if !group.admins.contains(&u.id) {
+            continue;
+        }
+        group_api::remove_user_group_admin(conf, &group.id, &u.id)
+            .await
+            .with_context(|| format!("cannot remove @{} from admins of {}", u.name, group.name))?;
+    }
+    Ok(())
+}
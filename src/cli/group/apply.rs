@@ -0,0 +1,292 @@
+use std::{collections::BTreeMap, fs};
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, group_api, me_api},
+    models::{PostUserGroupAdminRequest, PostUserGroupRequest, UserGroup, UserGroupMember},
+};
+use serde::Deserialize;
+
+use super::get_groups;
+use crate::cli::{prompt, user};
+
+/// Contents of a group file. Keys are group names.
+#[derive(Debug, Default, Deserialize)]
+pub struct GroupFile {
+    pub groups: BTreeMap<String, GroupSpec>,
+}
+
+/// `description` and `type` are used only when the group is created.
+/// `members` and `admins` are `@name`s and are left as they are if omitted.
+/// Roles are not managed: members are added without one and existing roles are kept.
+#[derive(Debug, Default, Deserialize)]
+pub struct GroupSpec {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, rename = "type")]
+    pub kind: String,
+    pub members: Option<Vec<String>>,
+    pub admins: Option<Vec<String>>,
+}
+
+/// Changes to one group. Users are `(id, name)`.
+#[derive(Debug, Default)]
+struct GroupPlan {
+    name: String,
+    /// `None` if the group is going to be created
+    id: Option<String>,
+    add_members: Vec<(String, String)>,
+    remove_members: Vec<(String, String)>,
+    add_admins: Vec<(String, String)>,
+    remove_admins: Vec<(String, String)>,
+}
+
+impl GroupPlan {
+    fn is_empty(&self) -> bool {
+        self.id.is_some()
+            && self.add_members.is_empty()
+            && self.remove_members.is_empty()
+            && self.add_admins.is_empty()
+            && self.remove_admins.is_empty()
+    }
+
+    fn print(&self) {
+        match self.id {
+            Some(_) => println!("~ {}", self.name),
+            None => println!("+ {}", self.name),
+        }
+        for (_, name) in self.add_members.iter() {
+            println!("    + @{}", name);
+        }
+        for (_, name) in self.remove_members.iter() {
+            println!("    - @{}", name);
+        }
+        for (_, name) in self.add_admins.iter() {
+            println!("    + admin @{}", name);
+        }
+        for (_, name) in self.remove_admins.iter() {
+            println!("    - admin @{}", name);
+        }
+    }
+}
+
+pub async fn apply(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let path = matches.value_of("file").unwrap();
+    let content = fs::read_to_string(path).with_context(|| format!("cannot read {}", path))?;
+    let file: GroupFile =
+        toml::from_str(&content).with_context(|| format!("cannot parse {}", path))?;
+
+    // resolve every name first so a typo fails before anything is changed
+    let mut ids = BTreeMap::new();
+    let mut names = BTreeMap::new();
+    for spec in file.groups.values() {
+        let users = spec.members.iter().chain(spec.admins.iter()).flatten();
+        for name in users {
+            if !ids.contains_key(name) {
+                let u = user::resolve(conf, name).await?;
+                names.insert(u.id.clone(), u.name);
+                ids.insert(name.clone(), u.id);
+            }
+        }
+    }
+    let to_ids = |users: &Option<Vec<String>>| {
+        users
+            .as_ref()
+            .map(|v| v.iter().map(|name| ids[name].clone()).collect::<Vec<_>>())
+    };
+
+    let current = get_groups(conf).await?;
+    // the server makes the creator a member and an admin of a new group
+    let creator = if file
+        .groups
+        .keys()
+        .any(|n| current.iter().all(|g| &g.name != n))
+    {
+        let me = me_api::get_me(conf)
+            .await
+            .with_context(|| "cannot get me")?;
+        names.insert(me.id.clone(), me.name);
+        vec![me.id]
+    } else {
+        Vec::new()
+    };
+    let named = |list: Vec<String>| {
+        list.into_iter()
+            .map(|id| {
+                let name = names.get(&id).cloned().unwrap_or_else(|| id.clone());
+                (id, name)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut plans = Vec::new();
+    for (name, spec) in file.groups.iter() {
+        let group = current.iter().find(|g| &g.name == name);
+        let (members, admins) = match group {
+            Some(g) => (
+                g.members.iter().map(|m| m.id.clone()).collect(),
+                g.admins.clone(),
+            ),
+            None => (creator.clone(), creator.clone()),
+        };
+        let mut plan = GroupPlan {
+            name: name.clone(),
+            id: group.map(|g| g.id.clone()),
+            ..GroupPlan::default()
+        };
+        if let Some(desired) = to_ids(&spec.members) {
+            let (add, remove) = diff(&members, &desired);
+            plan.add_members = named(add);
+            plan.remove_members = named(remove);
+        }
+        if let Some(desired) = to_ids(&spec.admins) {
+            let (add, remove) = diff(&admins, &desired);
+            plan.add_admins = named(add);
+            plan.remove_admins = named(remove);
+        }
+        if !plan.is_empty() {
+            plans.push(plan);
+        }
+    }
+
+    for plan in plans.iter() {
+        plan.print();
+    }
+    let created = plans.iter().filter(|p| p.id.is_none()).count();
+    println!(
+        "{} groups to create, {} to change",
+        created,
+        plans.len() - created
+    );
+    if plans.is_empty() {
+        return Ok(());
+    }
+    if !matches.is_present("yes") && !prompt::confirm("apply these changes?")? {
+        println!("canceled");
+        return Ok(());
+    }
+
+    for plan in plans {
+        let spec = &file.groups[&plan.name];
+        let group = match plan.id {
+            Some(ref id) => current.iter().find(|g| &g.id == id).unwrap().clone(),
+            None => create(conf, &plan.name, spec).await?,
+        };
+        sync(conf, &group, to_ids(&spec.members), to_ids(&spec.admins)).await?;
+    }
+
+    Ok(())
+}
+
+async fn create(conf: &Configuration, name: &str, spec: &GroupSpec) -> Result<UserGroup> {
+    let req =
+        PostUserGroupRequest::new(name.to_owned(), spec.description.clone(), spec.kind.clone());
+    let group = group_api::create_user_group(conf, Some(req))
+        .await
+        .with_context(|| format!("cannot create {}", name))?;
+    // the creator may have been made an admin
+    group_api::get_user_group(conf, &group.id)
+        .await
+        .with_context(|| format!("cannot get {}", name))
+}
+
+/// Makes members and admins of `group` match. Admins are added before any is removed,
+/// so the group is never left without one.
+async fn sync(
+    conf: &Configuration,
+    group: &UserGroup,
+    members: Option<Vec<String>>,
+    admins: Option<Vec<String>>,
+) -> Result<()> {
+    let context = || format!("cannot change {}", group.name);
+
+    if let Some(desired) = members {
+        let current: Vec<String> = group.members.iter().map(|m| m.id.clone()).collect();
+        let (add, remove) = diff(&current, &desired);
+        // compared by id only, see `GroupSpec`
+        for id in add {
+            let member = UserGroupMember::new(id, String::new());
+            group_api::add_user_group_member(conf, &group.id, Some(member))
+                .await
+                .with_context(context)?;
+        }
+        for id in remove {
+            group_api::remove_user_group_member(conf, &group.id, &id)
+                .await
+                .with_context(context)?;
+        }
+    }
+    if let Some(desired) = admins {
+        let (add, remove) = diff(&group.admins, &desired);
+        for id in add {
+            group_api::add_user_group_admin(
+                conf,
+                &group.id,
+                Some(PostUserGroupAdminRequest::new(id)),
+            )
+            .await
+            .with_context(context)?;
+        }
+        for id in remove {
+            group_api::remove_user_group_admin(conf, &group.id, &id)
+                .await
+                .with_context(context)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns ids to add and to remove, in the order of `desired` and `current`.
+fn diff(current: &[String], desired: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut add: Vec<String> = Vec::new();
+    for id in desired {
+        if !current.contains(id) && !add.contains(id) {
+            add.push(id.clone());
+        }
+    }
+    let remove = current
+        .iter()
+        .filter(|id| !desired.contains(id))
+        .cloned()
+        .collect();
+    (add, remove)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(
+            diff(&ids(&["a", "b", "c"]), &ids(&["c", "d", "a", "d"])),
+            (ids(&["d"]), ids(&["b"]))
+        );
+        assert_eq!(diff(&ids(&["a"]), &ids(&["a"])), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_parse_group_file() {
+        let file: GroupFile = toml::from_str(
+            r#"
+[groups.kaihatsu]
+description = "dev team"
+members = ["@a", "@b"]
+
+[groups.empty]
+"#,
+        )
+        .unwrap();
+        let dev = &file.groups["kaihatsu"];
+        assert_eq!(dev.description, "dev team");
+        assert_eq!(dev.members, Some(ids(&["@a", "@b"])));
+        assert_eq!(dev.admins, None);
+        assert_eq!(file.groups["empty"].members, None);
+    }
+}
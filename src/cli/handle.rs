@@ -1,4 +1,6 @@
-use super::{channel, notify};
+use super::{
+    channel, clip, export, file, group, me, msg, notify, pin, search, stamp, star, unread, user,
+};
 use anyhow::Result;
 use clap::ArgMatches;
 use rust_traq::apis::configuration::Configuration;
@@ -18,6 +20,18 @@ pub async fn handle_matches(
             }
         }
         "notify" => notify::notify(conf, matches).await,
+        "msg" => msg::msg(conf, matches).await,
+        "search" => search::search(conf, matches).await,
+        "export" => export::export(conf, matches).await,
+        "file" => file::file(conf, matches).await,
+        "stamp" => stamp::stamp(conf, matches).await,
+        "group" => group::group(conf, matches).await,
+        "me" => me::me(conf, matches).await,
+        "unread" => unread::unread(conf, matches).await,
+        "pin" => pin::pin(conf, matches).await,
+        "star" => star::star(conf, matches).await,
+        "clip" => clip::clip(conf, matches).await,
+        "user" => user::user(conf, matches).await,
         x => {
             dbg!("{}", x);
             Ok(())
@@ -0,0 +1,89 @@
+use anyhow::{bail, Result};
+
+/// Extracts the id from a raw UUID or a traQ permalink such as
+/// `https://q.trap.jp/messages/<uuid>`, where `kind` is `messages`.
+pub fn parse_id(s: &str, kind: &str) -> Result<String> {
+    let s = s.trim();
+    let id = if s.contains("://") {
+        let path = s.split(&['?', '#'][..]).next().unwrap_or_default();
+        let mut segments = path.trim_end_matches('/').rsplit('/');
+        match (segments.next(), segments.next()) {
+            (Some(id), Some(k)) if k == kind => id,
+            _ => bail!("not a link to {}: {}", kind, s),
+        }
+    } else {
+        s
+    };
+
+    if !is_uuid(id) {
+        bail!("invalid id: {}", id);
+    }
+    Ok(id.to_lowercase())
+}
+
+pub fn message_id(s: &str) -> Result<String> {
+    parse_id(s, "messages")
+}
+
+pub fn file_id(s: &str) -> Result<String> {
+    parse_id(s, "files")
+}
+
+/// Ids of file links like `https://q.trap.jp/files/<uuid>` in message content.
+pub fn file_ids(text: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    for (i, _) in text.match_indices("/files/") {
+        let id = text[i + "/files/".len()..]
+            .get(..36)
+            .unwrap_or_default()
+            .to_lowercase();
+        if is_uuid(&id) && !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+fn is_uuid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip(&[8, 4, 4, 4, 12])
+            .all(|(g, &len)| g.len() == len && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "0123abcd-4567-89ab-cdef-0123456789ab";
+
+    #[test]
+    fn test_message_id() {
+        assert_eq!(message_id(ID).unwrap(), ID);
+        assert_eq!(
+            message_id(&format!("https://q.trap.jp/messages/{}", ID)).unwrap(),
+            ID
+        );
+        assert_eq!(
+            message_id(&format!(
+                "https://q.trap.jp/messages/{}/?a=b#c",
+                ID.to_uppercase()
+            ))
+            .unwrap(),
+            ID
+        );
+        assert!(message_id(&format!("https://q.trap.jp/files/{}", ID)).is_err());
+        assert!(message_id("0123abcd").is_err());
+    }
+
+    #[test]
+    fn test_file_ids() {
+        let text = format!(
+            "see https://q.trap.jp/files/{}\nhttps://q.trap.jp/files/{}/ and /files/xyz",
+            ID, ID
+        );
+        assert_eq!(file_ids(&text), vec![ID.to_owned()]);
+    }
+}
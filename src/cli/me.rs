@@ -0,0 +1,171 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, me_api},
+    models::{PatchMeRequest, PatchUserTagRequest, PostUserTagRequest, UserTag},
+};
+use serde::Serialize;
+
+use super::{
+    channel, msg,
+    output::{self, OutputFormat, Render},
+    user::{self, UserProfile},
+};
+
+#[derive(Debug, Serialize)]
+pub struct TagEntry {
+    pub id: String,
+    pub tag: String,
+    pub locked: bool,
+}
+
+impl Render for TagEntry {
+    fn header() -> &'static [&'static str] {
+        &["id", "tag", "locked"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![self.id.clone(), self.tag.clone(), self.locked.to_string()]
+    }
+
+    fn text(&self) -> String {
+        if self.locked {
+            format!("{}  [locked]", self.tag)
+        } else {
+            self.tag.clone()
+        }
+    }
+}
+
+pub async fn me(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("set", Some(m)) => set(conf, m).await,
+        ("icon", Some(m)) => icon(conf, m).await,
+        ("tag", Some(m)) => match m.subcommand() {
+            ("ls", Some(m)) => tag_ls(conf, m).await,
+            ("add", Some(m)) => tag_add(conf, m).await,
+            ("rm", Some(m)) => tag_rm(conf, m).await,
+            ("lock", Some(m)) => tag_lock(conf, m, true).await,
+            ("unlock", Some(m)) => tag_lock(conf, m, false).await,
+            (x, _) => bail!("unknown tag command '{}'", x),
+        },
+        _ => show(conf, matches).await,
+    }
+}
+
+async fn show(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let me = me_api::get_me(conf)
+        .await
+        .with_context(|| "cannot get me")?;
+
+    let profile = UserProfile {
+        id: me.id,
+        name: me.name,
+        display_name: me.display_name,
+        bot: me.bot,
+        state: user::state_name(me.state),
+        bio: me.bio,
+        twitter_id: me.twitter_id,
+        groups: user::group_names(conf, &me.groups).await?,
+        tags: me.tags.into_iter().map(|t| t.tag).collect(),
+        last_online: me.last_online,
+        home_channel: user::channel_path(conf, me.home_channel).await?,
+    };
+    output::render(format, &[profile])
+}
+
+async fn set(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let mut req = PatchMeRequest::new();
+    req.display_name = matches.value_of("display-name").map(str::to_owned);
+    req.bio = matches.value_of("bio").map(str::to_owned);
+    req.twitter_id = matches
+        .value_of("twitter")
+        .map(|id| id.trim_start_matches('@').to_owned());
+    if let Some(name) = matches.value_of("home-channel") {
+        let mut tree = channel::get_channel_tree(conf).await?;
+        req.home_channel = Some(msg::channel_id(&mut tree, Some(name))?);
+    }
+    if req.display_name.is_none()
+        && req.bio.is_none()
+        && req.twitter_id.is_none()
+        && req.home_channel.is_none()
+    {
+        bail!("nothing to change");
+    }
+
+    me_api::edit_me(conf, Some(req))
+        .await
+        .with_context(|| "cannot edit profile")?;
+
+    Ok(())
+}
+
+async fn icon(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let path = PathBuf::from(matches.value_of("image").unwrap());
+    if !path.is_file() {
+        bail!("{} is not found", path.display());
+    }
+
+    me_api::change_my_icon(conf, path.clone())
+        .await
+        .with_context(|| format!("cannot change icon to {}", path.display()))?;
+
+    Ok(())
+}
+
+async fn get_tags(conf: &Configuration) -> Result<Vec<UserTag>> {
+    me_api::get_my_user_tags(conf)
+        .await
+        .with_context(|| "cannot get tags")
+}
+
+/// My tag named `tag`.
+async fn find_tag(conf: &Configuration, tag: &str) -> Result<UserTag> {
+    get_tags(conf)
+        .await?
+        .into_iter()
+        .find(|t| t.tag == tag)
+        .with_context(|| format!("tag not found: {}", tag))
+}
+
+async fn tag_ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let tags: Vec<TagEntry> = get_tags(conf)
+        .await?
+        .into_iter()
+        .map(|t| TagEntry {
+            id: t.tag_id,
+            tag: t.tag,
+            locked: t.is_locked,
+        })
+        .collect();
+    output::render(format, &tags)
+}
+
+async fn tag_add(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let tag = matches.value_of("tag").unwrap();
+    me_api::add_my_user_tag(conf, Some(PostUserTagRequest::new(tag.to_owned())))
+        .await
+        .with_context(|| format!("cannot add {}", tag))?;
+    Ok(())
+}
+
+async fn tag_rm(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let tag = find_tag(conf, matches.value_of("tag").unwrap()).await?;
+    me_api::remove_my_user_tag(conf, &tag.tag_id)
+        .await
+        .with_context(|| format!("cannot remove {}", tag.tag))?;
+    Ok(())
+}
+
+/// Locked tags cannot be removed by other users.
+async fn tag_lock(conf: &Configuration, matches: &ArgMatches<'_>, lock: bool) -> Result<()> {
+    let tag = find_tag(conf, matches.value_of("tag").unwrap()).await?;
+    me_api::edit_my_user_tag(conf, &tag.tag_id, Some(PatchUserTagRequest::new(lock)))
+        .await
+        .with_context(|| format!("cannot change {}", tag.tag))?;
+    Ok(())
+}
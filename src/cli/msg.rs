@@ -0,0 +1,53 @@
+mod embed;
+mod ls;
+mod manage;
+mod post;
+mod tail;
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{apis::configuration::Configuration, models::User};
+
+use super::{
+    channel::{self, ChannelTree},
+    user,
+};
+pub(crate) use ls::{format_time, MessageView};
+
+pub async fn msg(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("post", Some(m)) => post::post(conf, m).await,
+        ("ls", Some(m)) => ls::ls(conf, m).await,
+        ("tail", Some(m)) => tail::tail(conf, m).await,
+        ("edit", Some(m)) => manage::edit(conf, m).await,
+        ("rm", Some(m)) => manage::rm(conf, m).await,
+        (x, _) => bail!("unknown msg command '{}'", x),
+    }
+}
+
+/// Id of `channel_name`, or of the channel saved by `channel cd` if it is not given.
+pub(crate) fn channel_id(tree: &mut ChannelTree, channel_name: Option<&str>) -> Result<String> {
+    let id = match channel_name {
+        Some(name) => tree
+            .name_to_id(Path::new(name))
+            .with_context(|| format!("channel name not found: {}", name))?,
+        // the saved channel may have been deleted or moved since
+        None => tree
+            .name_to_id(Path::new(&channel::load_current_channel()))
+            .unwrap_or_default(),
+    };
+    // only the root has no id
+    if id.is_empty() {
+        bail!("specify a channel or change the current channel with `qui channel cd`");
+    }
+    Ok(id)
+}
+
+/// All users including suspended ones by id. They are always fetched, so new and renamed
+/// users are shown correctly, and the cache is refreshed on the way.
+pub(crate) async fn get_users(conf: &Configuration) -> Result<BTreeMap<String, User>> {
+    let users = user::get_users(conf).await?;
+    Ok(users.into_iter().map(|u| (u.id.clone(), u)).collect())
+}
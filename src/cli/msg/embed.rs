@@ -0,0 +1,108 @@
+use serde::Deserialize;
+use serde_json::Deserializer;
+
+/// Replaces `@user` and `#channel/path` with traQ embeds.
+/// Mentions which cannot be resolved are kept as they are.
+pub fn embed<U, C>(text: &str, mut user_id: U, mut channel_id: C) -> String
+where
+    U: FnMut(&str) -> Option<String>,
+    C: FnMut(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut prev = None;
+    while let Some(c) = rest.chars().next() {
+        // `a@b` is not a mention
+        let boundary = !matches!(prev, Some(p) if is_name_char(p));
+        if boundary && (c == '@' || c == '#') {
+            let body = &rest[1..];
+            let len = body
+                .find(|ch| !(is_name_char(ch) || (c == '#' && ch == '/')))
+                .unwrap_or(body.len());
+            let name = body[..len].trim_end_matches('/');
+            let found = match c {
+                _ if name.is_empty() => None,
+                '@' => user_id(name).map(|id| ("user", id)),
+                _ => channel_id(name).map(|id| ("channel", id)),
+            };
+            if let Some((kind, id)) = found {
+                out.push_str(&format!(
+                    r#"!{{"type":"{}","raw":"{}{}","id":"{}"}}"#,
+                    kind, c, name, id
+                ));
+                rest = &body[name.len()..];
+                prev = name.chars().last();
+                continue;
+            }
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+        prev = Some(c);
+    }
+    out
+}
+
+/// An embed like `!{"type":"user","raw":"@name","id":"..."}` in message content.
+#[derive(Debug, Deserialize)]
+pub struct Embed {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub raw: String,
+    pub id: String,
+}
+
+/// Replaces embeds with what `show` returns for them, or with their `raw` text.
+pub fn expand<F>(text: &str, mut show: F) -> String
+where
+    F: FnMut(&Embed) -> Option<String>,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find("!{") {
+        out.push_str(&rest[..i]);
+        let mut stream = Deserializer::from_str(&rest[i + 1..]).into_iter::<Embed>();
+        match stream.next() {
+            Some(Ok(embed)) => {
+                out.push_str(&show(&embed).unwrap_or(embed.raw));
+                rest = &rest[i + 1 + stream.byte_offset()..];
+            }
+            _ => {
+                out.push_str("!{");
+                rest = &rest[i + 2..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embed() {
+        let user = |name: &str| (name == "alice").then(|| "u1".to_owned());
+        let channel = |path: &str| (path == "gps/times").then(|| "c1".to_owned());
+
+        assert_eq!(
+            embed("hi @alice, see #gps/times.", user, channel),
+            r##"hi !{"type":"user","raw":"@alice","id":"u1"}, see !{"type":"channel","raw":"#gps/times","id":"c1"}."##
+        );
+        assert_eq!(
+            embed("a@alice @bob #random", user, channel),
+            "a@alice @bob #random"
+        );
+    }
+
+    #[test]
+    fn test_expand() {
+        let text = r#"hi !{"type":"user","raw":"@old","id":"u1"} !{"type":"file","raw":"f","id":"f1"} !{broken"#;
+        let show = |e: &Embed| (e.id == "u1").then(|| "@alice".to_owned());
+        assert_eq!(expand(text, show), "hi @alice f !{broken");
+    }
+}
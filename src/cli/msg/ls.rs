@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, message_api},
+    models::{Message, User},
+};
+use serde::Serialize;
+
+use super::{channel_id, embed, get_users};
+use crate::cli::{
+    channel, link,
+    output::{self, OutputFormat, Render},
+};
+
+/// The maximum `limit` of `get_messages`.
+const PAGE_SIZE: usize = 200;
+
+#[derive(Debug, Serialize)]
+pub struct MessageView {
+    pub id: String,
+    pub channel_id: String,
+    pub user_id: String,
+    pub user: String,
+    pub display_name: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub content: String,
+    /// `content` with embeds expanded
+    #[serde(skip)]
+    pub text: String,
+}
+
+impl MessageView {
+    pub fn new(
+        msg: Message,
+        users: &BTreeMap<String, User>,
+        paths: &BTreeMap<String, String>,
+    ) -> Self {
+        let text = embed::expand(&msg.content, |e| match e.kind.as_str() {
+            "user" => users.get(&e.id).map(|u| format!("@{}", u.name)),
+            "channel" => paths
+                .get(&e.id)
+                .map(|p| format!("#{}", p.trim_start_matches('/'))),
+            _ => None,
+        });
+        let (user, display_name) = match users.get(&msg.user_id) {
+            Some(u) => (u.name.clone(), u.display_name.clone()),
+            None => (msg.user_id.clone(), msg.user_id.clone()),
+        };
+        Self {
+            id: msg.id,
+            channel_id: msg.channel_id,
+            user_id: msg.user_id,
+            user,
+            display_name,
+            created_at: msg.created_at,
+            updated_at: msg.updated_at,
+            content: msg.content,
+            text,
+        }
+    }
+}
+
+impl Render for MessageView {
+    fn header() -> &'static [&'static str] {
+        &["id", "channel_id", "user", "created_at", "content"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.channel_id.clone(),
+            self.user.clone(),
+            self.created_at.clone(),
+            self.content.clone(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        let body: Vec<String> = self.text.lines().map(|l| format!("  {}", l)).collect();
+        format!(
+            "{}  {} (@{})\n{}",
+            format_time(&self.created_at),
+            self.display_name,
+            self.user,
+            body.join("\n")
+        )
+    }
+}
+
+/// `2021-06-01T12:34:56.789Z` -> `2021-06-01 12:34:56`
+pub fn format_time(ts: &str) -> String {
+    let ts = ts.split('.').next().unwrap_or(ts).trim_end_matches('Z');
+    ts.replacen('T', " ", 1)
+}
+
+pub async fn ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let id = channel_id(&mut tree, matches.value_of("channel_name"))?;
+
+    let limit: usize = matches
+        .value_of("limit")
+        .unwrap()
+        .parse()
+        .with_context(|| "limit must be a number")?;
+    let since = matches.value_of("since").map(str::to_owned);
+    let mut until = matches.value_of("until").map(str::to_owned);
+    if let Some(before) = matches.value_of("before") {
+        let before = link::message_id(before)?;
+        let msg = message_api::get_message(conf, &before)
+            .await
+            .with_context(|| format!("cannot get message {}", before))?;
+        until = match until {
+            Some(until) if until < msg.created_at => Some(until),
+            _ => Some(msg.created_at),
+        };
+    }
+
+    let mut messages = get_messages(conf, &id, limit, since, until).await?;
+    // messages are fetched newest first but read oldest first
+    if !matches.is_present("reverse") {
+        messages.reverse();
+    }
+
+    let users = get_users(conf).await?;
+    let paths = tree.paths();
+    let views: Vec<MessageView> = messages
+        .into_iter()
+        .map(|m| MessageView::new(m, &users, &paths))
+        .collect();
+    output::render(format, &views)
+}
+
+/// The latest `limit` messages between `since` and `until`, newest first.
+async fn get_messages(
+    conf: &Configuration,
+    channel_id: &str,
+    limit: usize,
+    since: Option<String>,
+    until: Option<String>,
+) -> Result<Vec<Message>> {
+    let mut messages = Vec::new();
+    while messages.len() < limit {
+        let n = (limit - messages.len()).min(PAGE_SIZE);
+        let page = message_api::get_messages(
+            conf,
+            channel_id,
+            Some(n as i32),
+            Some(messages.len() as i32),
+            since.clone(),
+            until.clone(),
+            None,
+            Some("desc"),
+        )
+        .await
+        .with_context(|| "cannot get messages")?;
+        let done = page.len() < n;
+        messages.extend(page);
+        if done {
+            break;
+        }
+    }
+    Ok(messages)
+}
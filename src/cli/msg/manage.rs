@@ -0,0 +1,70 @@
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, message_api},
+    models::PostMessageRequest,
+};
+
+use super::{get_users, ls::format_time};
+use crate::{
+    cli::{link, prompt},
+    utils::editor,
+};
+
+pub async fn edit(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let id = link::message_id(matches.value_of("message").unwrap())?;
+    let msg = message_api::get_message(conf, &id)
+        .await
+        .with_context(|| format!("cannot get message {}", id))?;
+
+    let content = editor::edit(&msg.content)?;
+    let content = content.trim_end();
+    if content.trim().is_empty() {
+        bail!("message is empty. use `qui msg rm` to delete it");
+    }
+    if content == msg.content.trim_end() {
+        println!("not changed");
+        return Ok(());
+    }
+
+    message_api::edit_message(conf, &id, Some(PostMessageRequest::new(content.to_owned())))
+        .await
+        .with_context(|| "cannot edit message")?;
+
+    Ok(())
+}
+
+pub async fn rm(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let ids = matches
+        .values_of("messages")
+        .unwrap()
+        .map(link::message_id)
+        .collect::<Result<Vec<String>>>()?;
+
+    let users = get_users(conf).await?;
+    for id in ids.iter() {
+        let msg = message_api::get_message(conf, id)
+            .await
+            .with_context(|| format!("cannot get message {}", id))?;
+        let user = users.get(&msg.user_id).map_or(&msg.user_id, |u| &u.name);
+        println!(
+            "{}  @{}: {}",
+            format_time(&msg.created_at),
+            user,
+            msg.content.lines().next().unwrap_or_default()
+        );
+    }
+    if !matches.is_present("yes") && !prompt::confirm("delete these messages?")? {
+        println!("canceled");
+        return Ok(());
+    }
+
+    for id in ids.iter() {
+        message_api::delete_message(conf, id)
+            .await
+            .with_context(|| format!("cannot delete message {}", id))?;
+    }
+    println!("deleted {} messages", ids.len());
+
+    Ok(())
+}
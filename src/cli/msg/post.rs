@@ -0,0 +1,56 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Read},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, message_api},
+    models::PostMessageRequest,
+};
+
+use super::{channel_id, embed::embed, get_users};
+use crate::{cli::channel, utils::editor};
+
+pub async fn post(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let id = channel_id(&mut tree, matches.value_of("channel_name"))?;
+
+    let text = match matches.value_of("text") {
+        Some(text) => text.to_owned(),
+        None if !termion::is_tty(&io::stdin()) => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        }
+        None => editor::edit("")?,
+    };
+    let text = text.trim_end();
+    if text.trim().is_empty() {
+        bail!("message is empty");
+    }
+
+    let content = if matches.is_present("embed") {
+        let users: BTreeMap<String, String> = get_users(conf)
+            .await?
+            .into_values()
+            .map(|u| (u.name, u.id))
+            .collect();
+        embed(
+            text,
+            |name| users.get(name).cloned(),
+            |path| tree.name_to_id(&Path::new("/").join(path)).ok(),
+        )
+    } else {
+        text.to_owned()
+    };
+
+    let msg = message_api::post_message(conf, &id, Some(PostMessageRequest::new(content)))
+        .await
+        .with_context(|| "cannot post message")?;
+    println!("{}", msg.id);
+
+    Ok(())
+}
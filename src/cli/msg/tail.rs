@@ -0,0 +1,253 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use futures::{SinkExt, StreamExt};
+use rust_traq::{
+    apis::{configuration::Configuration, message_api},
+    models::{ChannelSubscribeLevel, User},
+};
+use serde::{Deserialize, Serialize};
+use tokio::time;
+use tokio_tungstenite::tungstenite::{
+    client::IntoClientRequest,
+    http::{header::AUTHORIZATION, HeaderValue},
+    Message,
+};
+
+use super::{channel_id, get_users, ls::MessageView};
+use crate::cli::{
+    channel, notify,
+    output::{OutputFormat, Render},
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// An event pushed by the traQ WebSocket.
+#[derive(Debug, Deserialize)]
+struct WsEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    body: serde_json::Value,
+}
+
+/// A line of the `jsonl` output.
+#[derive(Debug, Serialize)]
+struct TailEvent<'a> {
+    #[serde(rename = "type")]
+    kind: &'a str,
+    id: &'a str,
+    channel: Option<&'a str>,
+    message: Option<&'a MessageView>,
+}
+
+/// Which channels to show.
+enum Filter {
+    All,
+    Channels(BTreeSet<String>),
+}
+
+struct Tail {
+    filter: Filter,
+    format: OutputFormat,
+    users: BTreeMap<String, User>,
+    paths: BTreeMap<String, String>,
+    /// channels of the messages shown so far, as deletion events only carry the id.
+    shown: BTreeMap<String, String>,
+}
+
+pub async fn tail(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let paths = tree.paths();
+
+    let filter = if matches.is_present("all") {
+        Filter::All
+    } else if matches.is_present("subscribed") {
+        let ids = notify::get_subscriptions(conf)
+            .await?
+            .into_iter()
+            .filter(|(_, level)| *level != ChannelSubscribeLevel::none)
+            .map(|(id, _)| id)
+            .collect();
+        Filter::Channels(ids)
+    } else {
+        let ids = match matches.values_of("channel_names") {
+            Some(names) => names
+                .map(|name| channel_id(&mut tree, Some(name)))
+                .collect::<Result<BTreeSet<String>>>()?,
+            None => vec![channel_id(&mut tree, None)?].into_iter().collect(),
+        };
+        Filter::Channels(ids)
+    };
+
+    let mut tail = Tail {
+        filter,
+        format,
+        users: get_users(conf).await?,
+        paths,
+        shown: BTreeMap::new(),
+    };
+
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        match tail.listen(conf, &mut backoff).await {
+            Ok(()) => eprintln!("disconnected"),
+            Err(e) => eprintln!("disconnected: {:#}", e),
+        }
+        eprintln!("reconnecting in {}s", backoff.as_secs());
+        time::delay_for(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// `https://q.trap.jp/api/v3` -> `wss://q.trap.jp/api/v3/ws`
+fn ws_url(base_path: &str) -> Result<String> {
+    let base_path = base_path.trim_end_matches('/');
+    let url = if let Some(rest) = base_path.strip_prefix("https://") {
+        format!("wss://{}/ws", rest)
+    } else if let Some(rest) = base_path.strip_prefix("http://") {
+        format!("ws://{}/ws", rest)
+    } else {
+        bail!("unsupported server url: {}", base_path)
+    };
+    Ok(url)
+}
+
+impl Tail {
+    /// Streams events until the connection is closed. `backoff` is reset once connected.
+    async fn listen(&mut self, conf: &Configuration, backoff: &mut Duration) -> Result<()> {
+        let token = conf
+            .oauth_access_token
+            .as_ref()
+            .with_context(|| "not logged in")?;
+        let mut req = ws_url(&conf.base_path)?.into_client_request()?;
+        req.headers_mut().insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token))?,
+        );
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(req)
+            .await
+            .with_context(|| "cannot connect to websocket")?;
+        // messages of all public channels are pushed while timeline streaming is on
+        ws.send(Message::Text("timeline_streaming:on".to_owned()))
+            .await?;
+        *backoff = INITIAL_BACKOFF;
+        eprintln!("connected");
+
+        while let Some(msg) = ws.next().await {
+            match msg? {
+                Message::Text(text) => {
+                    let event: WsEvent = match serde_json::from_str(&text) {
+                        Ok(event) => event,
+                        Err(_) => continue,
+                    };
+                    // a message that cannot be fetched should not drop the connection
+                    if let Err(e) = self.handle(conf, event).await {
+                        eprintln!("{:#}", e);
+                    }
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle(&mut self, conf: &Configuration, event: WsEvent) -> Result<()> {
+        let id = match event.body["id"].as_str() {
+            Some(id) => id.to_owned(),
+            None => return Ok(()),
+        };
+        match event.kind.as_str() {
+            "MESSAGE_CREATED" | "MESSAGE_UPDATED" => {
+                // skip the fetch when the event already tells the channel
+                if let Some(channel_id) = event.body["channel_id"].as_str() {
+                    if !self.wants(channel_id) {
+                        return Ok(());
+                    }
+                }
+                let msg = message_api::get_message(conf, &id)
+                    .await
+                    .with_context(|| format!("cannot get message {}", id))?;
+                if !self.wants(&msg.channel_id) {
+                    return Ok(());
+                }
+                if !self.users.contains_key(&msg.user_id) {
+                    self.users = get_users(conf).await?;
+                }
+                self.shown.insert(msg.id.clone(), msg.channel_id.clone());
+                let channel_id = msg.channel_id.clone();
+                let view = MessageView::new(msg, &self.users, &self.paths);
+                self.print(&event.kind, &id, Some(&channel_id), Some(&view));
+            }
+            "MESSAGE_DELETED" => {
+                let channel_id = self.shown.remove(&id);
+                if channel_id.is_some() || matches!(self.filter, Filter::All) {
+                    self.print(&event.kind, &id, channel_id.as_deref(), None);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn wants(&self, channel_id: &str) -> bool {
+        match &self.filter {
+            Filter::All => true,
+            Filter::Channels(ids) => ids.contains(channel_id),
+        }
+    }
+
+    fn print(&self, kind: &str, id: &str, channel_id: Option<&str>, view: Option<&MessageView>) {
+        let channel = channel_id
+            .and_then(|ch| self.paths.get(ch))
+            .map(String::as_str);
+        match self.format {
+            OutputFormat::Json | OutputFormat::Jsonl => {
+                let event = TailEvent {
+                    kind,
+                    id,
+                    channel,
+                    message: view,
+                };
+                if let Ok(line) = serde_json::to_string(&event) {
+                    println!("{}", line);
+                }
+            }
+            _ => {
+                let channel = channel.unwrap_or_default();
+                match (kind, view) {
+                    ("MESSAGE_UPDATED", Some(view)) => {
+                        println!("{} (edited) {}", channel, view.text())
+                    }
+                    (_, Some(view)) => println!("{} {}", channel, view.text()),
+                    (_, None) => println!("{} (deleted) {}", channel, id),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ws_url() {
+        assert_eq!(
+            ws_url("https://q.trap.jp/api/v3").unwrap(),
+            "wss://q.trap.jp/api/v3/ws"
+        );
+        assert_eq!(
+            ws_url("http://localhost:3000/api/v3/").unwrap(),
+            "ws://localhost:3000/api/v3/ws"
+        );
+        assert!(ws_url("q.trap.jp").is_err());
+    }
+}
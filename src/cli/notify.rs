@@ -1,4 +1,9 @@
+mod backup;
+mod plan;
+
 use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
     io::{self, Read},
     path::Path,
     time::Duration,
@@ -6,81 +11,318 @@ use std::{
 
 use anyhow::{bail, Context, Result};
 use clap::ArgMatches;
-use futures::future;
+use futures::{future, stream, StreamExt};
 use indicatif::ProgressBar;
 use rust_traq::{
-    apis::{configuration::Configuration, notification_api},
+    apis::{self, configuration::Configuration, notification_api},
     models::{ChannelSubscribeLevel, PutChannelSubscribeLevelRequest},
 };
+use serde::Serialize;
 use tokio::time;
 
-use super::channel;
+use super::{
+    channel,
+    output::{self, OutputFormat, Render},
+    prompt,
+};
+use plan::{Matcher, Plan, Rules, SubscriptionFile};
+
+/// Accepted by `parse_level`. Digits are kept for compatibility.
+pub const LEVELS: &[&str] = &["none", "unread", "on", "0", "1", "2"];
+
+pub fn level_name(level: ChannelSubscribeLevel) -> &'static str {
+    match level {
+        ChannelSubscribeLevel::none => "none",
+        ChannelSubscribeLevel::subscribed => "unread",
+        ChannelSubscribeLevel::notified => "on",
+    }
+}
+
+pub fn parse_level(level: &str) -> Result<ChannelSubscribeLevel> {
+    match level {
+        "none" | "0" => Ok(ChannelSubscribeLevel::none),
+        "unread" | "1" => Ok(ChannelSubscribeLevel::subscribed),
+        "on" | "2" => Ok(ChannelSubscribeLevel::notified),
+        x => bail!("unknown level '{}'. level must be none, unread or on", x),
+    }
+}
+
+pub(crate) async fn get_subscriptions(
+    conf: &Configuration,
+) -> Result<BTreeMap<String, ChannelSubscribeLevel>> {
+    let subscriptions = notification_api::get_my_channel_subscriptions(conf)
+        .await
+        .with_context(|| "cannot get subscriptions")?;
+    let mp = subscriptions
+        .into_iter()
+        .map(|s| (s.channel_id, s.level))
+        .collect();
+    Ok(mp)
+}
+
+#[derive(Debug, Serialize)]
+pub struct Subscription {
+    pub id: String,
+    pub path: String,
+    pub level: &'static str,
+}
+
+impl Render for Subscription {
+    fn header() -> &'static [&'static str] {
+        &["id", "path", "level"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![self.id.clone(), self.path.clone(), self.level.to_owned()]
+    }
+
+    fn text(&self) -> String {
+        format!("{:<6} {}", self.level, self.path)
+    }
+}
 
 pub async fn notify(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("show", Some(m)) => return show(conf, m).await,
+        ("export", Some(m)) => return export(conf, m).await,
+        ("apply", Some(m)) => return apply(conf, m).await,
+        ("backup", Some(m)) => return backup::backup(conf, m).await,
+        ("restore", Some(m)) => return backup::restore(conf, m).await,
+        _ => {}
+    }
+
     if let Some(level) = matches.value_of("level") {
-        let level = level
-            .parse::<u8>()
-            .with_context(|| "level must be 0, 1 or 2")?;
+        let level = parse_level(level)?;
         let mut tree = channel::get_channel_tree(conf).await?;
 
-        let ids = if let Some(ids) = matches.values_of("channel_names") {
-            ids.into_iter()
-                .map(|v| tree.name_to_id(Path::new(v)))
-                .collect::<Result<Vec<String>>>()
-                .with_context(|| "channel name not found")?
+        let names: Vec<String> = if let Some(names) = matches.values_of("channel_names") {
+            names.map(|v| v.to_owned()).collect()
         } else {
-            let mut ids = String::new();
-            io::stdin().read_to_string(&mut ids)?;
-            let ids: Vec<String> = ids
-                .split_whitespace()
-                .map(|v| tree.name_to_id(Path::new(v)))
-                .collect::<Result<Vec<String>>>()
-                .with_context(|| "channel name not found")?;
-            ids
+            let mut names = String::new();
+            io::stdin().read_to_string(&mut names)?;
+            names.split_whitespace().map(|v| v.to_owned()).collect()
+        };
+
+        let excludes = match matches.values_of("exclude") {
+            Some(v) => v.map(Matcher::new).collect::<Result<Vec<Matcher>>>()?,
+            None => Vec::new(),
         };
+        let recursive = matches.is_present("recursive");
+        let skip_archived = matches.is_present("skip-archived");
 
-        let ids: Vec<(String, u8)> = ids.into_iter().map(|v| (v, level)).collect();
+        let mut seen = BTreeSet::new();
+        let mut changes = Vec::new();
+        for name in names.iter() {
+            let entries = tree
+                .entries(Path::new(name), recursive)
+                .with_context(|| format!("channel name not found: {}", name))?;
+            for e in entries {
+                if (skip_archived && e.archived)
+                    || excludes.iter().any(|m| m.matches_subtree(&e.path))
+                    || !seen.insert(e.id.clone())
+                {
+                    continue;
+                }
+                changes.push(SubscriptionChange {
+                    id: e.id,
+                    path: e.path,
+                    level,
+                });
+            }
+        }
 
-        set_subscriptions(conf, ids).await?;
+        if matches.is_present("dry-run") {
+            for c in changes.iter() {
+                println!("{:<6} {}", level_name(c.level), c.path);
+            }
+            println!("\n{} channels would be changed", changes.len());
+        } else {
+            set_subscriptions(conf, changes).await?;
+        }
     }
 
     Ok(())
 }
 
+async fn show(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let levels = get_subscriptions(conf).await?;
+
+    let recursive = matches.is_present("recursive");
+    let entries = match matches.values_of("channel_names") {
+        Some(names) => {
+            let mut entries = Vec::new();
+            for name in names {
+                entries.extend(tree.entries(Path::new(name), recursive)?);
+            }
+            entries
+        }
+        None => tree
+            .entries(Path::new("/"), true)?
+            .into_iter()
+            .filter(|e| levels.contains_key(&e.id))
+            .collect(),
+    };
+
+    let subscriptions: Vec<Subscription> = entries
+        .into_iter()
+        .map(|e| Subscription {
+            level: level_name(
+                levels
+                    .get(&e.id)
+                    .copied()
+                    .unwrap_or(ChannelSubscribeLevel::none),
+            ),
+            id: e.id,
+            path: e.path,
+        })
+        .collect();
+    output::render(format, &subscriptions)?;
+
+    let count = |level| subscriptions.iter().filter(|s| s.level == level).count();
+    let summary = format!(
+        "{} channels: on {}, unread {}, none {}",
+        subscriptions.len(),
+        count("on"),
+        count("unread"),
+        count("none")
+    );
+    // keep stdout parsable for structured formats
+    if format.is_text() {
+        println!("\n{}", summary);
+    } else {
+        eprintln!("{}", summary);
+    }
+
+    Ok(())
+}
+
+async fn export(conf: &Configuration, _matches: &ArgMatches<'_>) -> Result<()> {
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let levels = get_subscriptions(conf).await?;
+
+    let subscriptions = tree
+        .entries(Path::new("/"), true)?
+        .into_iter()
+        .filter_map(|e| match levels.get(&e.id) {
+            Some(&level) if level != ChannelSubscribeLevel::none => {
+                Some((e.path, level_name(level).to_owned()))
+            }
+            _ => None,
+        })
+        .collect();
+    let file = SubscriptionFile { subscriptions };
+    print!(
+        "{}",
+        toml::to_string(&file).with_context(|| "serialize error")?
+    );
+
+    Ok(())
+}
+
+async fn apply(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let path = matches.value_of("file").unwrap();
+    let content = fs::read_to_string(path).with_context(|| format!("cannot read {}", path))?;
+    let file: SubscriptionFile =
+        toml::from_str(&content).with_context(|| format!("cannot parse {}", path))?;
+    let rules = Rules::new(&file)?;
+
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let entries = tree.entries(Path::new("/"), true)?;
+    rules.check_paths(&entries)?;
+
+    let current = get_subscriptions(conf).await?;
+    let desired = entries
+        .iter()
+        .filter_map(|e| rules.level(&e.path).map(|level| (e.id.clone(), level)))
+        .collect();
+
+    let plan = Plan::new(&entries, &current, &desired);
+    plan.print();
+    if plan.is_empty() {
+        return Ok(());
+    }
+    if !matches.is_present("yes") && !prompt::confirm("apply these changes?")? {
+        println!("canceled");
+        return Ok(());
+    }
+
+    set_subscriptions(conf, plan.into_changes()).await
+}
+
+/// A channel whose notification level is going to be changed.
+#[derive(Debug, Clone)]
+pub struct SubscriptionChange {
+    pub id: String,
+    pub path: String,
+    pub level: ChannelSubscribeLevel,
+}
+
+const CONCURRENCY: usize = 4;
+const MAX_RETRIES: u32 = 5;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
 pub async fn set_subscriptions(
     conf: &Configuration,
-    channel_ids_and_subscribe_level: Vec<(String, u8)>,
+    changes: Vec<SubscriptionChange>,
 ) -> Result<()> {
-    let id_levels: Vec<(String, PutChannelSubscribeLevelRequest)> = channel_ids_and_subscribe_level
-        .into_iter()
-        .map(|(id, level)| {
-            let level = match level {
-                0 => PutChannelSubscribeLevelRequest::new(ChannelSubscribeLevel::none),
-                1 => PutChannelSubscribeLevelRequest::new(ChannelSubscribeLevel::subscribed),
-
-                2 => PutChannelSubscribeLevelRequest::new(ChannelSubscribeLevel::notified),
-                _ => bail!("subscribe level must be 0, 1 or 2"),
-            };
-            Ok((id, level))
+    let total = changes.len();
+    let pb = ProgressBar::new(total as u64);
+    let pb = &pb;
+    let mut failures: Vec<(String, String)> = stream::iter(changes)
+        .map(|change| async move {
+            let level = PutChannelSubscribeLevelRequest::new(change.level);
+            let res = set_subscription_with_retry(conf, &change.id, level).await;
+            pb.inc(1);
+            res.err().map(|e| (change.path, e))
         })
-        .collect::<Result<Vec<(String, PutChannelSubscribeLevelRequest)>>>()?;
-
-    let pb = ProgressBar::new(id_levels.len() as u64);
-    let mut handles = Vec::with_capacity(id_levels.len());
-    for (i, (channel_id, level)) in id_levels.iter().enumerate() {
-        let handle =
-            notification_api::set_channel_subscribe_level(conf, channel_id, Some(level.clone()));
-        handles.push(handle);
-        pb.inc(1);
-
-        if i % 5 == 0 {
-            time::delay_for(Duration::from_millis(100)).await;
+        .buffer_unordered(CONCURRENCY)
+        .filter_map(future::ready)
+        .collect()
+        .await;
+    pb.finish();
+
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    failures.sort();
+    eprintln!("failed to change {} of {} channels:", failures.len(), total);
+    for (path, e) in failures.iter() {
+        eprintln!("  {}: {}", path, e);
+    }
+    bail!("{} channels were not changed", failures.len())
+}
+
+/// Retries with exponential backoff while the server is rate limiting or failing.
+async fn set_subscription_with_retry(
+    conf: &Configuration,
+    channel_id: &str,
+    level: PutChannelSubscribeLevelRequest,
+) -> std::result::Result<(), String> {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut retries = 0;
+    loop {
+        match notification_api::set_channel_subscribe_level(conf, channel_id, Some(level.clone()))
+            .await
+        {
+            Ok(()) => return Ok(()),
+            Err(apis::Error::ResponseError(ref res))
+                if is_retryable(res.status.as_u16()) && retries < MAX_RETRIES =>
+            {
+                retries += 1;
+                time::delay_for(backoff).await;
+                backoff *= 2;
+            }
+            Err(apis::Error::ResponseError(res)) => {
+                return Err(format!("{} {}", res.status, res.content.trim()))
+            }
+            Err(e) => return Err(e.to_string()),
         }
     }
-    let res = future::join_all(handles).await;
-    pb.finish();
+}
 
-    res.into_iter()
-        .try_for_each(|v| v)
-        .with_context(|| "some error occurred")
+fn is_retryable(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
 }
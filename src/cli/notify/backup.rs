@@ -0,0 +1,179 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, DirBuilder},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, star_api},
+    models::{ChannelSubscribeLevel, PostStarRequest},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{get_subscriptions, level_name, parse_level, plan::Plan, set_subscriptions};
+use crate::{
+    cli::{
+        channel::{self, ChannelEntry},
+        prompt,
+    },
+    config,
+};
+
+/// A snapshot of notification settings.
+/// traQ v3 has no per-channel mute, so only levels and stars are saved.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backup {
+    pub created_at: u64,
+    pub subscriptions: Vec<SavedSubscription>,
+    pub stars: Vec<SavedStar>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedSubscription {
+    pub id: String,
+    pub path: String,
+    pub level: String,
+}
+
+/// `path` is only for reading the file. channels are restored by id.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedStar {
+    pub id: String,
+    pub path: String,
+}
+
+pub fn backup_dir() -> Result<PathBuf> {
+    let mut path = config::data_dir_path()?;
+    path.push("backups");
+    Ok(path)
+}
+
+pub async fn backup(conf: &Configuration, _matches: &ArgMatches<'_>) -> Result<()> {
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let entries = tree.entries(Path::new("/"), true)?;
+    let paths: BTreeMap<&str, &str> = entries
+        .iter()
+        .map(|e| (e.id.as_str(), e.path.as_str()))
+        .collect();
+    let path_of = |id: &str| paths.get(id).copied().unwrap_or_default().to_owned();
+
+    let subscriptions = get_subscriptions(conf)
+        .await?
+        .into_iter()
+        .filter(|(_, level)| *level != ChannelSubscribeLevel::none)
+        .map(|(id, level)| SavedSubscription {
+            path: path_of(&id),
+            level: level_name(level).to_owned(),
+            id,
+        })
+        .collect();
+    let stars = star_api::get_my_stars(conf)
+        .await
+        .with_context(|| "cannot get stars")?
+        .into_iter()
+        .map(|id| SavedStar {
+            path: path_of(&id),
+            id,
+        })
+        .collect();
+
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let backup = Backup {
+        created_at,
+        subscriptions,
+        stars,
+    };
+
+    let dir = backup_dir()?;
+    DirBuilder::new().recursive(true).create(&dir)?;
+    let path = dir.join(format!("notify-{}.json", created_at));
+    let content = serde_json::to_string_pretty(&backup).with_context(|| "serialize error")?;
+    fs::write(&path, content).with_context(|| format!("cannot write {}", path.display()))?;
+    println!(
+        "saved {} subscriptions and {} stars to {}",
+        backup.subscriptions.len(),
+        backup.stars.len(),
+        path.display()
+    );
+
+    Ok(())
+}
+
+pub async fn restore(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let path = matches.value_of("file").unwrap();
+    let content = fs::read_to_string(path).with_context(|| format!("cannot read {}", path))?;
+    let backup: Backup =
+        serde_json::from_str(&content).with_context(|| format!("cannot parse {}", path))?;
+
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let entries = tree.entries(Path::new("/"), true)?;
+    let ids: BTreeSet<&str> = entries.iter().map(|e| e.id.as_str()).collect();
+    for s in backup.subscriptions.iter() {
+        if !ids.contains(s.id.as_str()) {
+            eprintln!("warning: {} no longer exists", s.path);
+        }
+    }
+
+    let current = get_subscriptions(conf).await?;
+    let desired = backup
+        .subscriptions
+        .iter()
+        .map(|s| {
+            let level =
+                parse_level(&s.level).with_context(|| format!("invalid level of {}", s.path))?;
+            Ok((s.id.clone(), level))
+        })
+        .collect::<Result<BTreeMap<String, ChannelSubscribeLevel>>>()?;
+    let plan = Plan::new(&entries, &current, &desired);
+
+    let current_stars: BTreeSet<String> = star_api::get_my_stars(conf)
+        .await
+        .with_context(|| "cannot get stars")?
+        .into_iter()
+        .collect();
+    let desired_stars: BTreeSet<String> = backup.stars.into_iter().map(|s| s.id).collect();
+    let star = star_changes(&entries, &current_stars, &desired_stars);
+    let unstar = star_changes(&entries, &desired_stars, &current_stars);
+
+    plan.print();
+    for e in star.iter() {
+        println!("+ star {}", e.path);
+    }
+    for e in unstar.iter() {
+        println!("- star {}", e.path);
+    }
+    if plan.is_empty() && star.is_empty() && unstar.is_empty() {
+        return Ok(());
+    }
+    if !matches.is_present("yes") && !prompt::confirm("restore these settings?")? {
+        println!("canceled");
+        return Ok(());
+    }
+
+    for e in star {
+        star_api::add_my_star(conf, Some(PostStarRequest::new(e.id.clone())))
+            .await
+            .with_context(|| format!("cannot star {}", e.path))?;
+    }
+    for e in unstar {
+        star_api::remove_my_star(conf, &e.id)
+            .await
+            .with_context(|| format!("cannot unstar {}", e.path))?;
+    }
+    set_subscriptions(conf, plan.into_changes()).await
+}
+
+/// Entries in `to` but not in `from`.
+fn star_changes<'a>(
+    entries: &'a [ChannelEntry],
+    from: &BTreeSet<String>,
+    to: &BTreeSet<String>,
+) -> Vec<&'a ChannelEntry> {
+    entries
+        .iter()
+        .filter(|e| to.contains(&e.id) && !from.contains(&e.id))
+        .collect()
+}
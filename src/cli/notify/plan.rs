@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use glob::{MatchOptions, Pattern};
+use rust_traq::models::ChannelSubscribeLevel;
+use serde::{Deserialize, Serialize};
+
+use super::{level_name, parse_level, SubscriptionChange};
+use crate::cli::channel::ChannelEntry;
+
+/// Contents of a subscription file. Keys are channel paths or globs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SubscriptionFile {
+    pub subscriptions: BTreeMap<String, String>,
+}
+
+/// A channel path or a glob of channel paths.
+pub enum Matcher {
+    Exact(String),
+    Glob(Pattern),
+}
+
+impl Matcher {
+    pub fn new(key: &str) -> Result<Self> {
+        if key.contains(&['*', '?', '['][..]) {
+            let pattern = Pattern::new(key).with_context(|| format!("invalid glob {}", key))?;
+            Ok(Self::Glob(pattern))
+        } else {
+            Ok(Self::Exact(key.trim_end_matches('/').to_owned()))
+        }
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let options = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        match self {
+            Self::Exact(p) => p == path,
+            Self::Glob(p) => p.matches_with(path, options),
+        }
+    }
+
+    /// Like `matches`, but also matches the descendants of a matched path.
+    pub fn matches_subtree(&self, path: &str) -> bool {
+        match self {
+            Self::Exact(p) => path == p || path.starts_with(&format!("{}/", p)),
+            // `path` itself and every ancestor of it
+            Self::Glob(_) => path
+                .match_indices('/')
+                .skip(1)
+                .map(|(i, _)| &path[..i])
+                .chain(std::iter::once(path))
+                .any(|p| self.matches(p)),
+        }
+    }
+}
+
+pub struct Rules {
+    /// Sorted from the most specific rule.
+    rules: Vec<(Matcher, ChannelSubscribeLevel)>,
+}
+
+impl Rules {
+    pub fn new(file: &SubscriptionFile) -> Result<Self> {
+        let mut rules = file
+            .subscriptions
+            .iter()
+            .map(|(key, level)| {
+                let level =
+                    parse_level(level).with_context(|| format!("invalid level of {}", key))?;
+                Ok((Matcher::new(key)?, level))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // exact paths win over globs, and longer globs over shorter ones
+        rules.sort_by_key(|(m, _)| match m {
+            Matcher::Exact(_) => (0, 0),
+            Matcher::Glob(p) => (1, usize::MAX - p.as_str().len()),
+        });
+        Ok(Self { rules })
+    }
+
+    pub fn level(&self, path: &str) -> Option<ChannelSubscribeLevel> {
+        self.rules
+            .iter()
+            .find(|(m, _)| m.matches(path))
+            .map(|(_, level)| *level)
+    }
+
+    /// Fails if an exact path does not exist, which is likely a typo.
+    pub fn check_paths(&self, entries: &[ChannelEntry]) -> Result<()> {
+        for (m, _) in self.rules.iter() {
+            if let Matcher::Exact(p) = m {
+                if !entries.iter().any(|e| &e.path == p) {
+                    bail!("{} is not found", p);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub id: String,
+    pub path: String,
+    pub from: ChannelSubscribeLevel,
+    pub to: ChannelSubscribeLevel,
+}
+
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub added: Vec<Change>,
+    pub changed: Vec<Change>,
+    pub removed: Vec<Change>,
+}
+
+impl Plan {
+    /// Compares levels of `entries`. Channels missing from a map are not subscribed.
+    pub fn new(
+        entries: &[ChannelEntry],
+        current: &BTreeMap<String, ChannelSubscribeLevel>,
+        desired: &BTreeMap<String, ChannelSubscribeLevel>,
+    ) -> Self {
+        let mut plan = Self::default();
+        for entry in entries {
+            let from = current
+                .get(&entry.id)
+                .copied()
+                .unwrap_or(ChannelSubscribeLevel::none);
+            let to = desired
+                .get(&entry.id)
+                .copied()
+                .unwrap_or(ChannelSubscribeLevel::none);
+            if from == to {
+                continue;
+            }
+
+            let change = Change {
+                id: entry.id.clone(),
+                path: entry.path.clone(),
+                from,
+                to,
+            };
+            if from == ChannelSubscribeLevel::none {
+                plan.added.push(change);
+            } else if to == ChannelSubscribeLevel::none {
+                plan.removed.push(change);
+            } else {
+                plan.changed.push(change);
+            }
+        }
+        plan
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn print(&self) {
+        for c in self.added.iter() {
+            println!("+ {} ({})", c.path, level_name(c.to));
+        }
+        for c in self.changed.iter() {
+            println!(
+                "~ {} ({} -> {})",
+                c.path,
+                level_name(c.from),
+                level_name(c.to)
+            );
+        }
+        for c in self.removed.iter() {
+            println!("- {} ({})", c.path, level_name(c.from));
+        }
+        println!(
+            "{} to add, {} to change, {} to remove",
+            self.added.len(),
+            self.changed.len(),
+            self.removed.len()
+        );
+    }
+
+    pub fn into_changes(self) -> Vec<SubscriptionChange> {
+        self.added
+            .into_iter()
+            .chain(self.changed)
+            .chain(self.removed)
+            .map(|c| SubscriptionChange {
+                id: c.id,
+                path: c.path,
+                level: c.to,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ChannelSubscribeLevel::{none, notified, subscribed};
+
+    fn rules(rules: &[(&str, &str)]) -> Rules {
+        let file = SubscriptionFile {
+            subscriptions: rules
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        };
+        Rules::new(&file).unwrap()
+    }
+
+    #[test]
+    fn test_rules() {
+        let rules = rules(&[
+            ("/team/SysAd/**", "unread"),
+            ("/team/SysAd/bot", "none"),
+            ("/team/*", "on"),
+        ]);
+        assert_eq!(rules.level("/team/SysAd"), Some(notified));
+        assert_eq!(rules.level("/team/SysAd/bot"), Some(none));
+        assert_eq!(rules.level("/team/SysAd/a/b"), Some(subscribed));
+        assert_eq!(rules.level("/general"), None);
+    }
+
+    #[test]
+    fn test_matches_subtree() {
+        let bot = Matcher::new("/team/SysAd/bot").unwrap();
+        assert!(bot.matches_subtree("/team/SysAd/bot"));
+        assert!(bot.matches_subtree("/team/SysAd/bot/log"));
+        assert!(!bot.matches_subtree("/team/SysAd/bots"));
+
+        let glob = Matcher::new("/team/*/bot").unwrap();
+        assert!(glob.matches_subtree("/team/SysAd/bot"));
+        assert!(glob.matches_subtree("/team/SysAd/bot/log"));
+        assert!(!glob.matches_subtree("/team/SysAd/a/bot"));
+        assert!(!glob.matches_subtree("/team/SysAd"));
+    }
+
+    #[test]
+    fn test_plan() {
+        let entries: Vec<ChannelEntry> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|id| ChannelEntry::new(id.to_string(), format!("/{}", id)))
+            .collect();
+        let current = [("a", notified), ("b", subscribed), ("c", notified)];
+        let desired = [("a", notified), ("b", notified), ("d", subscribed)];
+        let to_map = |v: &[(&str, ChannelSubscribeLevel)]| {
+            v.iter()
+                .map(|(k, l)| (k.to_string(), *l))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let plan = Plan::new(&entries, &to_map(&current), &to_map(&desired));
+        let ids = |v: &[Change]| v.iter().map(|c| c.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&plan.added), vec!["d"]);
+        assert_eq!(ids(&plan.changed), vec!["b"]);
+        assert_eq!(ids(&plan.removed), vec!["c"]);
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+
+use anyhow::{bail, Result};
+use clap::ArgMatches;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Jsonl,
+    Csv,
+    Tree,
+}
+
+impl OutputFormat {
+    pub const VALUES: &'static [&'static str] = &["text", "json", "jsonl", "csv", "tree"];
+
+    pub fn from_matches(matches: &ArgMatches<'_>) -> Result<Self> {
+        match matches.value_of("output") {
+            Some(s) => s.parse(),
+            None => Ok(Self::Text),
+        }
+    }
+
+    pub fn is_text(self) -> bool {
+        self == Self::Text
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            "csv" => Ok(Self::Csv),
+            "tree" => Ok(Self::Tree),
+            x => bail!("unknown output format '{}'", x),
+        }
+    }
+}
+
+/// A record which can be printed in every `OutputFormat`.
+pub trait Render: Serialize {
+    /// Column names of the csv header.
+    fn header() -> &'static [&'static str];
+
+    /// Column values in the same order as `header`.
+    fn record(&self) -> Vec<String>;
+
+    /// A line for the `text` format.
+    fn text(&self) -> String;
+
+    /// Depth and label for the `tree` format.
+    fn tree(&self) -> (usize, String) {
+        (0, self.text())
+    }
+}
+
+pub fn render<T: Render>(format: OutputFormat, items: &[T]) -> Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    write(&mut out, format, items)
+}
+
+pub fn write<W: Write, T: Render>(w: &mut W, format: OutputFormat, items: &[T]) -> Result<()> {
+    match format {
+        OutputFormat::Text => {
+            for item in items {
+                writeln!(w, "{}", item.text())?;
+            }
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *w, items)?;
+            writeln!(w)?;
+        }
+        OutputFormat::Jsonl => {
+            for item in items {
+                serde_json::to_writer(&mut *w, item)?;
+                writeln!(w)?;
+            }
+        }
+        OutputFormat::Csv => {
+            writeln!(w, "{}", csv_line(T::header().iter().copied()))?;
+            for item in items {
+                let record = item.record();
+                writeln!(w, "{}", csv_line(record.iter().map(String::as_str)))?;
+            }
+        }
+        OutputFormat::Tree => {
+            for item in items {
+                let (depth, label) = item.tree();
+                writeln!(w, "{}{}", "  ".repeat(depth), label)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn csv_line<'a>(fields: impl Iterator<Item = &'a str>) -> String {
+    fields.map(csv_escape).collect::<Vec<String>>().join(",")
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Item {
+        name: String,
+        depth: usize,
+    }
+
+    impl Render for Item {
+        fn header() -> &'static [&'static str] {
+            &["name", "depth"]
+        }
+
+        fn record(&self) -> Vec<String> {
+            vec![self.name.clone(), self.depth.to_string()]
+        }
+
+        fn text(&self) -> String {
+            self.name.clone()
+        }
+
+        fn tree(&self) -> (usize, String) {
+            (self.depth, self.name.clone())
+        }
+    }
+
+    fn items() -> Vec<Item> {
+        vec![
+            Item {
+                name: "a".to_owned(),
+                depth: 0,
+            },
+            Item {
+                name: "b,\"c\"".to_owned(),
+                depth: 1,
+            },
+        ]
+    }
+
+    fn written(format: OutputFormat) -> String {
+        let mut buf = Vec::new();
+        write(&mut buf, format, &items()).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            written(OutputFormat::Csv),
+            "name,depth\na,0\n\"b,\"\"c\"\"\",1\n"
+        );
+    }
+
+    #[test]
+    fn test_jsonl() {
+        assert_eq!(
+            written(OutputFormat::Jsonl),
+            "{\"name\":\"a\",\"depth\":0}\n{\"name\":\"b,\\\"c\\\"\",\"depth\":1}\n"
+        );
+    }
+
+    #[test]
+    fn test_tree() {
+        assert_eq!(written(OutputFormat::Tree), "a\n  b,\"c\"\n");
+    }
+
+    #[test]
+    fn test_parse() {
+        for s in OutputFormat::VALUES {
+            assert!(s.parse::<OutputFormat>().is_ok());
+        }
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use termion::{event::Key, raw::IntoRawMode, screen::AlternateScreen};
+use tui::{
+    backend::TermionBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Style},
+    text::Span,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Terminal,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::utils::event::{self, Event, Events};
+
+/// Lets the user choose one of `items` while editing `query`.
+/// `rank` returns the indices of `items` matching a query, best first.
+///
+/// The picker is drawn on the tty, so it also works inside `$(...)`.
+pub fn pick<F>(title: &str, items: &[String], query: &str, rank: F) -> Result<Option<usize>>
+where
+    F: Fn(&str) -> Vec<usize>,
+{
+    let tty = termion::get_tty()?.into_raw_mode()?;
+    let backend = TermionBackend::new(AlternateScreen::from(tty));
+    let mut terminal = Terminal::new(backend)?;
+    let events = Events::with_config(event::Config {
+        exit_key: Key::Esc,
+        tick_rate: Duration::from_millis(250),
+    });
+
+    let mut query = query.to_owned();
+    let mut ranked = rank(&query);
+    let mut state = ListState::default();
+    state.select(if ranked.is_empty() { None } else { Some(0) });
+
+    loop {
+        terminal.draw(|f| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+                .split(f.size());
+
+            let input = Paragraph::new(query.as_str()).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" {} ", title)),
+            );
+            f.render_widget(input, chunks[0]);
+            f.set_cursor(chunks[0].x + query.width() as u16 + 1, chunks[0].y + 1);
+
+            let list_items: Vec<ListItem> = ranked
+                .iter()
+                .map(|&i| ListItem::new(Span::raw(items[i].as_str())))
+                .collect();
+            let list = List::new(list_items)
+                .block(Block::default().borders(Borders::ALL).title(format!(
+                    " {}/{} ",
+                    ranked.len(),
+                    items.len()
+                )))
+                .highlight_style(Style::default().bg(Color::LightBlue));
+            f.render_stateful_widget(list, chunks[1], &mut state);
+        })?;
+
+        if let Event::Input(key) = events.next()? {
+            match key {
+                Key::Esc | Key::Ctrl('c') => return Ok(None),
+                Key::Char('\n') => return Ok(state.selected().map(|i| ranked[i])),
+                Key::Down | Key::Ctrl('n') => {
+                    if let Some(i) = state.selected() {
+                        state.select(Some((i + 1).min(ranked.len() - 1)));
+                    }
+                }
+                Key::Up | Key::Ctrl('p') => {
+                    if let Some(i) = state.selected() {
+                        state.select(Some(i.saturating_sub(1)));
+                    }
+                }
+                Key::Char(c) => {
+                    query.push(c);
+                    ranked = rank(&query);
+                    state.select(if ranked.is_empty() { None } else { Some(0) });
+                }
+                Key::Backspace => {
+                    query.pop();
+                    ranked = rank(&query);
+                    state.select(if ranked.is_empty() { None } else { Some(0) });
+                }
+                _ => {}
+            }
+        }
+    }
+}
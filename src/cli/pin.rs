@@ -0,0 +1,102 @@
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::apis::{channel_api, configuration::Configuration, pin_api};
+use serde::Serialize;
+
+use super::{
+    channel, link,
+    msg::{self, format_time, MessageView},
+    output::{self, OutputFormat, Render},
+};
+
+#[derive(Debug, Serialize)]
+pub struct PinEntry {
+    /// `@name` of the user who pinned the message
+    pub pinned_by: String,
+    pub pinned_at: String,
+    #[serde(flatten)]
+    pub message: MessageView,
+}
+
+impl Render for PinEntry {
+    fn header() -> &'static [&'static str] {
+        &[
+            "id",
+            "user",
+            "created_at",
+            "pinned_by",
+            "pinned_at",
+            "content",
+        ]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.message.id.clone(),
+            self.message.user.clone(),
+            self.message.created_at.clone(),
+            self.pinned_by.clone(),
+            self.pinned_at.clone(),
+            self.message.content.clone(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!(
+            "{}\n  (pinned by {} {})",
+            self.message.text(),
+            self.pinned_by,
+            format_time(&self.pinned_at)
+        )
+    }
+}
+
+pub async fn pin(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("ls", Some(m)) => ls(conf, m).await,
+        ("add", Some(m)) => add(conf, m).await,
+        ("rm", Some(m)) => rm(conf, m).await,
+        (x, _) => bail!("unknown pin command '{}'", x),
+    }
+}
+
+async fn ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let channel_id = msg::channel_id(&mut tree, matches.value_of("channel_name"))?;
+
+    let mut pins = channel_api::get_channel_pins(conf, &channel_id)
+        .await
+        .with_context(|| "cannot get pins")?;
+    pins.sort_by(|a, b| a.pinned_at.cmp(&b.pinned_at));
+
+    let users = msg::get_users(conf).await?;
+    let paths = tree.paths();
+    let entries: Vec<PinEntry> = pins
+        .into_iter()
+        .map(|p| PinEntry {
+            pinned_by: users
+                .get(&p.user_id)
+                .map_or_else(|| p.user_id.clone(), |u| format!("@{}", u.name)),
+            pinned_at: p.pinned_at,
+            message: MessageView::new(p.message, &users, &paths),
+        })
+        .collect();
+    output::render(format, &entries)
+}
+
+async fn add(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let id = link::message_id(matches.value_of("message").unwrap())?;
+    pin_api::create_pin(conf, &id)
+        .await
+        .with_context(|| format!("cannot pin {}", id))?;
+    Ok(())
+}
+
+async fn rm(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let id = link::message_id(matches.value_of("message").unwrap())?;
+    pin_api::remove_pin(conf, &id)
+        .await
+        .with_context(|| format!("cannot unpin {}", id))?;
+    Ok(())
+}
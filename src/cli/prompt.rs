@@ -0,0 +1,12 @@
+use std::io::{self, Write};
+
+use anyhow::Result;
+
+/// Asks a yes/no question on stdin. Anything but yes is no.
+pub fn confirm(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(matches!(input.trim(), "y" | "Y" | "yes"))
+}
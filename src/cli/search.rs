@@ -0,0 +1,194 @@
+use std::io;
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use rust_traq::apis::{configuration::Configuration, message_api};
+use serde::Serialize;
+use termion::{color, style};
+
+use super::{
+    channel,
+    msg::{self, MessageView},
+    output::{self, OutputFormat, Render},
+    user,
+};
+
+/// The maximum `limit` of `search_messages`.
+const PAGE_SIZE: usize = 100;
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub channel: String,
+    #[serde(flatten)]
+    pub message: MessageView,
+}
+
+impl Render for SearchHit {
+    fn header() -> &'static [&'static str] {
+        &["id", "channel", "user", "created_at", "content"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.message.id.clone(),
+            self.channel.clone(),
+            self.message.user.clone(),
+            self.message.created_at.clone(),
+            self.message.content.clone(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!("{}  {}", self.channel, self.message.text())
+    }
+}
+
+pub async fn search(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let query = matches.value_of("query").unwrap();
+    let limit: usize = matches
+        .value_of("limit")
+        .unwrap()
+        .parse()
+        .with_context(|| "limit must be a number")?;
+    let offset: usize = matches
+        .value_of("offset")
+        .unwrap()
+        .parse()
+        .with_context(|| "offset must be a number")?;
+
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let channel_id = match matches.value_of("in") {
+        Some(name) => Some(msg::channel_id(&mut tree, Some(name))?),
+        None => None,
+    };
+    let users = msg::get_users(conf).await?;
+    let from = match matches.value_of("from") {
+        Some(name) => Some(user::resolve(conf, name).await?.id),
+        None => None,
+    };
+    let bot = if matches.is_present("bot") {
+        Some(true)
+    } else if matches.is_present("no-bot") {
+        Some(false)
+    } else {
+        None
+    };
+    let has_attachments = if matches.is_present("has-attachment") {
+        Some(true)
+    } else {
+        None
+    };
+
+    let mut messages = Vec::new();
+    let mut total = 0;
+    while messages.len() < limit {
+        let n = (limit - messages.len()).min(PAGE_SIZE);
+        let res = message_api::search_messages(
+            conf,
+            Some(query),
+            matches.value_of("after").map(str::to_owned),
+            matches.value_of("before").map(str::to_owned),
+            channel_id.as_deref(),
+            None,
+            from.as_deref(),
+            None,
+            bot,
+            None,
+            has_attachments,
+            None,
+            None,
+            None,
+            Some(n as i32),
+            Some((offset + messages.len()) as i32),
+            None,
+        )
+        .await
+        .with_context(|| "cannot search messages")?;
+        total = res.total_hits as usize;
+        let done = res.hits.len() < n;
+        messages.extend(res.hits);
+        if done {
+            break;
+        }
+    }
+
+    let paths = tree.paths();
+    let mut hits: Vec<SearchHit> = messages
+        .into_iter()
+        .map(|m| SearchHit {
+            channel: paths.get(&m.channel_id).cloned().unwrap_or_default(),
+            message: MessageView::new(m, &users, &paths),
+        })
+        .collect();
+
+    if !format.is_text() {
+        return output::render(format, &hits);
+    }
+
+    if termion::is_tty(&io::stdout()) {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        let start = format!("{}{}", style::Bold, color::Fg(color::Yellow));
+        let end = format!("{}{}", color::Fg(color::Reset), style::Reset);
+        for hit in hits.iter_mut() {
+            hit.message.text = highlight(&hit.message.text, &words, &start, &end);
+        }
+    }
+    output::render(format, &hits)?;
+
+    if hits.is_empty() {
+        println!("no hits");
+    } else {
+        println!("\n{}-{} of {} hits", offset + 1, offset + hits.len(), total);
+    }
+
+    Ok(())
+}
+
+/// Wraps every case-insensitive occurrence of `words` in `start` and `end`.
+fn highlight(text: &str, words: &[&str], start: &str, end: &str) -> String {
+    // ascii lowercase keeps byte offsets
+    let lower = text.to_ascii_lowercase();
+    let words: Vec<String> = words
+        .iter()
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_ascii_lowercase())
+        .collect();
+
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < text.len() {
+        let found = words.iter().find(|w| lower[i..].starts_with(w.as_str()));
+        match found {
+            Some(w) => {
+                out.push_str(start);
+                out.push_str(&text[i..i + w.len()]);
+                out.push_str(end);
+                i += w.len();
+            }
+            None => {
+                let c = text[i..].chars().next().unwrap();
+                out.push(c);
+                i += c.len_utf8();
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(
+            highlight("Rust と rust と RUST", &["rust"], "[", "]"),
+            "[Rust] と [rust] と [RUST]"
+        );
+        assert_eq!(
+            highlight("こんにちは", &["にち", ""], "[", "]"),
+            "こん[にち]は"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+mod catalog;
+mod palette;
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, message_api, stamp_api},
+    models::{PostMessageStampRequest, Stamp},
+};
+use serde::Serialize;
+
+use super::{
+    link, msg,
+    output::{self, OutputFormat, Render},
+};
+use crate::cache;
+
+const STAMPS_CACHE: &str = "stamps";
+
+#[derive(Debug, Serialize)]
+pub struct StampCount {
+    pub stamp: String,
+    pub count: i32,
+    /// `@name` of users who stamped, with their counts
+    pub users: Vec<(String, i32)>,
+}
+
+impl Render for StampCount {
+    fn header() -> &'static [&'static str] {
+        &["stamp", "count", "users"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.stamp.clone(),
+            self.count.to_string(),
+            self.users_text(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!(":{}: {}  {}", self.stamp, self.count, self.users_text())
+    }
+}
+
+impl StampCount {
+    fn users_text(&self) -> String {
+        self.users
+            .iter()
+            .map(|(name, count)| match count {
+                1 => name.clone(),
+                n => format!("{}({})", name, n),
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+pub async fn stamp(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("add", Some(m)) => add(conf, m).await,
+        ("rm", Some(m)) => rm(conf, m).await,
+        ("ls", Some(m)) if m.is_present("message") => ls(conf, m).await,
+        ("ls", Some(m)) => catalog::list(conf, m).await,
+        ("find", Some(m)) => catalog::find(conf, m).await,
+        ("show", Some(m)) => catalog::show(conf, m).await,
+        ("upload", Some(m)) => catalog::upload(conf, m).await,
+        ("rename", Some(m)) => catalog::rename(conf, m).await,
+        ("delete", Some(m)) => catalog::delete(conf, m).await,
+        ("palette", Some(m)) => palette::palette(conf, m).await,
+        (x, _) => bail!("unknown stamp command '{}'", x),
+    }
+}
+
+/// Fetches all stamps and refreshes the cache.
+pub(crate) async fn get_stamps(conf: &Configuration) -> Result<Vec<Stamp>> {
+    let stamps = stamp_api::get_stamps(conf, Some(true))
+        .await
+        .with_context(|| "cannot get stamps")?;
+    let _ = cache::store(STAMPS_CACHE, &stamps);
+    Ok(stamps)
+}
+
+/// Cached stamps, fetched only if there is no cache yet.
+pub(crate) async fn get_cached_stamps(conf: &Configuration) -> Result<Vec<Stamp>> {
+    match cache::load(STAMPS_CACHE) {
+        Ok(stamps) => Ok(stamps),
+        Err(_) => get_stamps(conf).await,
+    }
+}
+
+/// Looks up a stamp by `name` or `:name:`. The cache is refreshed once if it is not found.
+pub(crate) async fn resolve(conf: &Configuration, name: &str) -> Result<Stamp> {
+    let name = name.trim_matches(':');
+    let stamps = get_cached_stamps(conf).await?;
+    if let Some(stamp) = stamps.into_iter().find(|s| s.name == name) {
+        return Ok(stamp);
+    }
+    get_stamps(conf)
+        .await?
+        .into_iter()
+        .find(|s| s.name == name)
+        .with_context(|| format!("stamp not found: :{}:", name))
+}
+
+async fn add(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let message_id = link::message_id(matches.value_of("message").unwrap())?;
+    let stamp = resolve(conf, matches.value_of("stamp").unwrap()).await?;
+    let count: i32 = matches
+        .value_of("count")
+        .unwrap()
+        .parse()
+        .with_context(|| "count must be a number")?;
+
+    message_api::add_message_stamp(
+        conf,
+        &message_id,
+        &stamp.id,
+        Some(PostMessageStampRequest::new(count)),
+    )
+    .await
+    .with_context(|| format!("cannot add :{}:", stamp.name))?;
+
+    Ok(())
+}
+
+async fn rm(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let message_id = link::message_id(matches.value_of("message").unwrap())?;
+    let stamp = resolve(conf, matches.value_of("stamp").unwrap()).await?;
+
+    message_api::remove_message_stamp(conf, &message_id, &stamp.id)
+        .await
+        .with_context(|| format!("cannot remove :{}:", stamp.name))?;
+
+    Ok(())
+}
+
+async fn ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let message_id = link::message_id(matches.value_of("message").unwrap())?;
+    let mut stamps = message_api::get_message_stamps(conf, &message_id)
+        .await
+        .with_context(|| format!("cannot get stamps of {}", message_id))?;
+    stamps.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    let mut names = get_cached_stamps(conf).await?;
+    if stamps
+        .iter()
+        .any(|s| !names.iter().any(|n| n.id == s.stamp_id))
+    {
+        names = get_stamps(conf).await?;
+    }
+    let users = msg::get_users(conf).await?;
+
+    // in the order each stamp was first added, like the traQ client
+    let mut counts: Vec<(String, StampCount)> = Vec::new();
+    for s in stamps {
+        let user = users
+            .get(&s.user_id)
+            .map_or_else(|| s.user_id.clone(), |u| format!("@{}", u.name));
+        let i = match counts.iter().position(|(id, _)| *id == s.stamp_id) {
+            Some(i) => i,
+            None => {
+                let name = names
+                    .iter()
+                    .find(|n| n.id == s.stamp_id)
+                    .map_or_else(|| s.stamp_id.clone(), |n| n.name.clone());
+                counts.push((
+                    s.stamp_id.clone(),
+                    StampCount {
+                        stamp: name,
+                        count: 0,
+                        users: Vec::new(),
+                    },
+                ));
+                counts.len() - 1
+            }
+        };
+        let c = &mut counts[i].1;
+        c.count += s.count;
+        c.users.push((user, s.count));
+    }
+
+    let counts: Vec<StampCount> = counts.into_iter().map(|(_, c)| c).collect();
+    output::render(format, &counts)
+}
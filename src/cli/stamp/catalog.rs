@@ -0,0 +1,273 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Read,
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, me_api, stamp_api},
+    models::{PatchStampRequest, Stamp, User},
+};
+use serde::Serialize;
+
+use super::{get_cached_stamps, get_stamps, resolve};
+use crate::{
+    cli::{
+        msg::{self, format_time},
+        output::{self, OutputFormat, Render},
+        prompt,
+    },
+    utils::fuzzy,
+};
+
+/// traQ rejects stamp images larger than this.
+const MAX_IMAGE_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct StampEntry {
+    pub id: String,
+    pub name: String,
+    pub creator: String,
+    pub is_unicode: bool,
+    pub file_id: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl StampEntry {
+    fn new(s: Stamp, users: &BTreeMap<String, User>) -> Self {
+        let creator = users
+            .get(&s.creator_id)
+            .map_or_else(|| s.creator_id.clone(), |u| format!("@{}", u.name));
+        Self {
+            id: s.id,
+            name: s.name,
+            creator,
+            is_unicode: s.is_unicode,
+            file_id: s.file_id,
+            created_at: s.created_at,
+            updated_at: s.updated_at,
+        }
+    }
+}
+
+impl Render for StampEntry {
+    fn header() -> &'static [&'static str] {
+        &[
+            "id",
+            "name",
+            "creator",
+            "is_unicode",
+            "file_id",
+            "created_at",
+            "updated_at",
+        ]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.creator.clone(),
+            self.is_unicode.to_string(),
+            self.file_id.clone(),
+            self.created_at.clone(),
+            self.updated_at.clone(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!(":{}:  {}", self.name, self.creator)
+    }
+}
+
+pub async fn list(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let stamps = if matches.is_present("refresh") {
+        get_stamps(conf).await?
+    } else {
+        get_cached_stamps(conf).await?
+    };
+    let unicode = matches.is_present("unicode");
+    let users = msg::get_users(conf).await?;
+
+    let mut entries: Vec<StampEntry> = stamps
+        .into_iter()
+        .filter(|s| unicode || !s.is_unicode)
+        .map(|s| StampEntry::new(s, &users))
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    output::render(format, &entries)
+}
+
+pub async fn find(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let query = matches.value_of("query").unwrap().trim_matches(':');
+    let limit: usize = matches
+        .value_of("limit")
+        .unwrap()
+        .parse()
+        .with_context(|| "limit must be a number")?;
+
+    let stamps = get_cached_stamps(conf).await?;
+    let users = msg::get_users(conf).await?;
+    let entries: Vec<StampEntry> = fuzzy::rank(query, stamps.iter().map(|s| s.name.as_str()))
+        .into_iter()
+        .take(limit)
+        .map(|(i, _)| StampEntry::new(stamps[i].clone(), &users))
+        .collect();
+    output::render(format, &entries)
+}
+
+pub async fn show(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let stamp = resolve(conf, matches.value_of("stamp").unwrap()).await?;
+    let users = msg::get_users(conf).await?;
+    let entry = StampEntry::new(stamp, &users);
+
+    if !format.is_text() {
+        return output::render(format, &[entry]);
+    }
+    println!(
+        "name:       :{}:\nid:         {}\ncreator:    {}\nunicode:    {}\nfile:       {}\ncreated at: {}\nupdated at: {}",
+        entry.name,
+        entry.id,
+        entry.creator,
+        entry.is_unicode,
+        entry.file_id,
+        format_time(&entry.created_at),
+        format_time(&entry.updated_at)
+    );
+    Ok(())
+}
+
+pub async fn upload(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let path = PathBuf::from(matches.value_of("file").unwrap());
+    let name = match matches.value_of("name") {
+        Some(name) => name.trim_matches(':').to_owned(),
+        None => path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .with_context(|| format!("invalid file name: {}", path.display()))?
+            .to_owned(),
+    };
+    check_name(&name)?;
+
+    let size = fs::metadata(&path)
+        .with_context(|| format!("cannot read {}", path.display()))?
+        .len();
+    if size > MAX_IMAGE_SIZE {
+        bail!(
+            "{} is {} bytes. stamps must be at most {} bytes",
+            path.display(),
+            size,
+            MAX_IMAGE_SIZE
+        );
+    }
+    let mut head = [0; 8];
+    let n = File::open(&path)?.read(&mut head)?;
+    if image_kind(&head[..n]).is_none() {
+        bail!("{} is not a PNG or GIF image", path.display());
+    }
+
+    let stamp = stamp_api::create_stamp(conf, &name, path)
+        .await
+        .with_context(|| format!("cannot create :{}:", name))?;
+    let _ = get_stamps(conf).await;
+    println!("created :{}: ({})", stamp.name, stamp.id);
+
+    Ok(())
+}
+
+pub async fn rename(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let stamp = resolve(conf, matches.value_of("stamp").unwrap()).await?;
+    let new_name = matches.value_of("new_name").unwrap().trim_matches(':');
+    check_name(new_name)?;
+    check_owner(conf, &stamp).await?;
+
+    let mut req = PatchStampRequest::new();
+    req.name = Some(new_name.to_owned());
+    stamp_api::edit_stamp(conf, &stamp.id, Some(req))
+        .await
+        .with_context(|| format!("cannot rename :{}:", stamp.name))?;
+    let _ = get_stamps(conf).await;
+    println!(":{}: -> :{}:", stamp.name, new_name);
+
+    Ok(())
+}
+
+pub async fn delete(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let stamp = resolve(conf, matches.value_of("stamp").unwrap()).await?;
+    check_owner(conf, &stamp).await?;
+    if !matches.is_present("yes") && !prompt::confirm(&format!("delete :{}:?", stamp.name))? {
+        println!("canceled");
+        return Ok(());
+    }
+
+    stamp_api::delete_stamp(conf, &stamp.id)
+        .await
+        .with_context(|| format!("cannot delete :{}:", stamp.name))?;
+    let _ = get_stamps(conf).await;
+    println!("deleted :{}:", stamp.name);
+
+    Ok(())
+}
+
+async fn check_owner(conf: &Configuration, stamp: &Stamp) -> Result<()> {
+    let me = me_api::get_me(conf)
+        .await
+        .with_context(|| "cannot get me")?;
+    if stamp.creator_id != me.id {
+        bail!(":{}: is not yours", stamp.name);
+    }
+    Ok(())
+}
+
+/// traQ accepts 1 to 32 alphanumerics, `_` and `-`.
+fn check_name(name: &str) -> Result<()> {
+    let valid = (1..=32).contains(&name.len())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        bail!(
+            "invalid stamp name '{}'. use 1 to 32 of a-z, A-Z, 0-9, _ and -",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Detects the image format from the magic bytes.
+fn image_kind(head: &[u8]) -> Option<&'static str> {
+    if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+        Some("gif")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_name() {
+        assert!(check_name("kusa_1-2").is_ok());
+        assert!(check_name("").is_err());
+        assert!(check_name("kusa!").is_err());
+        assert!(check_name(&"a".repeat(33)).is_err());
+    }
+
+    #[test]
+    fn test_image_kind() {
+        assert_eq!(image_kind(b"\x89PNG\r\n\x1a\n"), Some("png"));
+        assert_eq!(image_kind(b"GIF89a.."), Some("gif"));
+        assert_eq!(image_kind(b"\xff\xd8\xff\xe0"), None);
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, stamp_api},
+    models::{PatchStampPaletteRequest, PostStampPaletteRequest, StampPalette},
+};
+use serde::Serialize;
+
+use super::{get_cached_stamps, resolve};
+use crate::cli::{
+    output::{self, OutputFormat, Render},
+    prompt,
+};
+
+#[derive(Debug, Serialize)]
+pub struct PaletteEntry {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// stamp names
+    pub stamps: Vec<String>,
+}
+
+impl Render for PaletteEntry {
+    fn header() -> &'static [&'static str] {
+        &["id", "name", "description", "stamps"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.description.clone(),
+            self.stamps.join(" "),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!(
+            "{} ({} stamps)  {}",
+            self.name,
+            self.stamps.len(),
+            self.description
+        )
+    }
+}
+
+pub async fn palette(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("ls", Some(m)) => ls(conf, m).await,
+        ("show", Some(m)) => show(conf, m).await,
+        ("create", Some(m)) => create(conf, m).await,
+        ("edit", Some(m)) => edit(conf, m).await,
+        ("rm", Some(m)) => rm(conf, m).await,
+        (x, _) => bail!("unknown palette command '{}'", x),
+    }
+}
+
+async fn entries(conf: &Configuration, palettes: Vec<StampPalette>) -> Result<Vec<PaletteEntry>> {
+    let names: BTreeMap<String, String> = get_cached_stamps(conf)
+        .await?
+        .into_iter()
+        .map(|s| (s.id, s.name))
+        .collect();
+    let entries = palettes
+        .into_iter()
+        .map(|p| PaletteEntry {
+            stamps: p
+                .stamps
+                .iter()
+                .map(|id| names.get(id).cloned().unwrap_or_else(|| id.clone()))
+                .collect(),
+            id: p.id,
+            name: p.name,
+            description: p.description,
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// My palette named `name`.
+async fn find(conf: &Configuration, name: &str) -> Result<StampPalette> {
+    stamp_api::get_stamp_palettes(conf)
+        .await
+        .with_context(|| "cannot get palettes")?
+        .into_iter()
+        .find(|p| p.name == name)
+        .with_context(|| format!("palette not found: {}", name))
+}
+
+async fn stamp_ids(conf: &Configuration, names: Option<clap::Values<'_>>) -> Result<Vec<String>> {
+    let mut ids = Vec::new();
+    for name in names.into_iter().flatten() {
+        ids.push(resolve(conf, name).await?.id);
+    }
+    Ok(ids)
+}
+
+async fn ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let palettes = stamp_api::get_stamp_palettes(conf)
+        .await
+        .with_context(|| "cannot get palettes")?;
+    output::render(format, &entries(conf, palettes).await?)
+}
+
+async fn show(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let palette = find(conf, matches.value_of("palette").unwrap()).await?;
+    let entries = entries(conf, vec![palette]).await?;
+    if !format.is_text() {
+        return output::render(format, &entries);
+    }
+    for e in entries.iter() {
+        println!("{}", e.text());
+        let stamps: Vec<String> = e.stamps.iter().map(|s| format!(":{}:", s)).collect();
+        println!("{}", stamps.join(" "));
+    }
+    Ok(())
+}
+
+async fn create(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let name = matches.value_of("palette").unwrap();
+    let stamps = stamp_ids(conf, matches.values_of("stamps")).await?;
+    let description = matches.value_of("description").unwrap_or_default();
+
+    let req = PostStampPaletteRequest::new(stamps, name.to_owned(), description.to_owned());
+    let palette = stamp_api::create_stamp_palette(conf, Some(req))
+        .await
+        .with_context(|| format!("cannot create {}", name))?;
+    println!("created {} ({})", palette.name, palette.id);
+
+    Ok(())
+}
+
+async fn edit(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let palette = find(conf, matches.value_of("palette").unwrap()).await?;
+
+    let mut req = PatchStampPaletteRequest::new();
+    req.name = matches.value_of("name").map(str::to_owned);
+    req.description = matches.value_of("description").map(str::to_owned);
+    let add = stamp_ids(conf, matches.values_of("add")).await?;
+    let remove = stamp_ids(conf, matches.values_of("remove")).await?;
+    if !add.is_empty() || !remove.is_empty() {
+        let mut stamps: Vec<String> = palette
+            .stamps
+            .iter()
+            .filter(|id| !remove.contains(id))
+            .cloned()
+            .collect();
+        for id in add {
+            if !stamps.contains(&id) {
+                stamps.push(id);
+            }
+        }
+        req.stamps = Some(stamps);
+    }
+    if req.name.is_none() && req.description.is_none() && req.stamps.is_none() {
+        bail!("nothing to change");
+    }
+
+    stamp_api::edit_stamp_palette(conf, &palette.id, Some(req))
+        .await
+        .with_context(|| format!("cannot edit {}", palette.name))?;
+
+    Ok(())
+}
+
+async fn rm(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let palette = find(conf, matches.value_of("palette").unwrap()).await?;
+    if !matches.is_present("yes") && !prompt::confirm(&format!("delete palette {}?", palette.name))?
+    {
+        println!("canceled");
+        return Ok(());
+    }
+
+    stamp_api::delete_stamp_palette(conf, &palette.id)
+        .await
+        .with_context(|| format!("cannot delete {}", palette.name))?;
+    println!("deleted {}", palette.name);
+
+    Ok(())
+}
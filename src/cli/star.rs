@@ -0,0 +1,99 @@
+use std::{collections::BTreeSet, path::Path};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, star_api},
+    models::PostStarRequest,
+};
+use serde::Serialize;
+
+use super::{
+    channel, msg,
+    output::{self, OutputFormat, Render},
+};
+
+#[derive(Debug, Serialize)]
+pub struct StarEntry {
+    pub id: String,
+    pub path: String,
+}
+
+impl Render for StarEntry {
+    fn header() -> &'static [&'static str] {
+        &["id", "path"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![self.id.clone(), self.path.clone()]
+    }
+
+    fn text(&self) -> String {
+        self.path.clone()
+    }
+}
+
+pub async fn star(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("ls", Some(m)) => ls(conf, m).await,
+        ("add", Some(m)) => add(conf, m).await,
+        ("rm", Some(m)) => rm(conf, m).await,
+        (x, _) => bail!("unknown star command '{}'", x),
+    }
+}
+
+async fn get_stars(conf: &Configuration) -> Result<BTreeSet<String>> {
+    let stars = star_api::get_my_stars(conf)
+        .await
+        .with_context(|| "cannot get stars")?;
+    Ok(stars.into_iter().collect())
+}
+
+/// Starred channels under `channel_name`, or all of them, in the order of the tree.
+async fn ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let stars = get_stars(conf).await?;
+    let name = matches.value_of("channel_name").unwrap_or("/");
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let entries: Vec<StarEntry> = tree
+        .entries(Path::new(name), true)
+        .with_context(|| format!("channel name not found: {}", name))?
+        .into_iter()
+        .filter(|e| stars.contains(&e.id))
+        .map(|e| StarEntry {
+            id: e.id,
+            path: e.path,
+        })
+        .collect();
+    output::render(format, &entries)
+}
+
+async fn add(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let stars = get_stars(conf).await?;
+    let mut tree = channel::get_channel_tree(conf).await?;
+    for name in matches.values_of("channel_names").unwrap() {
+        let id = msg::channel_id(&mut tree, Some(name))?;
+        if stars.contains(&id) {
+            continue;
+        }
+        star_api::add_my_star(conf, Some(PostStarRequest::new(id)))
+            .await
+            .with_context(|| format!("cannot star {}", name))?;
+    }
+    Ok(())
+}
+
+async fn rm(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let stars = get_stars(conf).await?;
+    let mut tree = channel::get_channel_tree(conf).await?;
+    for name in matches.values_of("channel_names").unwrap() {
+        let id = msg::channel_id(&mut tree, Some(name))?;
+        if !stars.contains(&id) {
+            continue;
+        }
+        star_api::remove_my_star(conf, &id)
+            .await
+            .with_context(|| format!("cannot unstar {}", name))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,251 @@
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use futures::{future, stream, StreamExt};
+use indicatif::ProgressBar;
+use rust_traq::{
+    apis::{configuration::Configuration, notification_api},
+    models::UnreadChannel,
+};
+use serde::Serialize;
+
+use super::{
+    channel::{self, ChannelEntry, ChannelTree},
+    msg::format_time,
+    output::{self, OutputFormat, Render},
+};
+
+const CONCURRENCY: usize = 4;
+
+#[derive(Debug, Serialize)]
+pub struct UnreadEntry {
+    pub id: String,
+    pub path: String,
+    pub count: i32,
+    /// mentioned or otherwise notified
+    pub mention: bool,
+    /// time of the oldest unread message
+    pub since: String,
+}
+
+impl Render for UnreadEntry {
+    fn header() -> &'static [&'static str] {
+        &["id", "path", "count", "mention", "since"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.path.clone(),
+            self.count.to_string(),
+            self.mention.to_string(),
+            self.since.clone(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        format!(
+            "{:>5} {} {}  {}",
+            self.count,
+            if self.mention { "@" } else { " " },
+            format_time(&self.since),
+            self.path
+        )
+    }
+}
+
+pub async fn unread(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("read", Some(m)) => read(conf, m).await,
+        _ => ls(conf, matches).await,
+    }
+}
+
+async fn get_unread_entries(
+    conf: &Configuration,
+    tree: &mut ChannelTree,
+) -> Result<Vec<UnreadEntry>> {
+    let unread = notification_api::get_my_unread_channels(conf)
+        .await
+        .with_context(|| "cannot get unread channels")?;
+    Ok(merge(tree.entries(Path::new("/"), true)?, unread))
+}
+
+/// Unread channels in the order of the channel tree. Channels outside the tree, such as
+/// DMs, come last with their ids as paths.
+fn merge(tree: Vec<ChannelEntry>, unread: Vec<UnreadChannel>) -> Vec<UnreadEntry> {
+    let mut unread: BTreeMap<String, UnreadChannel> = unread
+        .into_iter()
+        .map(|u| (u.channel_id.clone(), u))
+        .collect();
+
+    let mut entries = Vec::new();
+    for e in tree {
+        if let Some(u) = unread.remove(&e.id) {
+            entries.push(UnreadEntry {
+                id: e.id,
+                path: e.path,
+                count: u.count,
+                mention: u.noticeable,
+                since: u.since,
+            });
+        }
+    }
+    entries.extend(unread.into_iter().map(|(id, u)| UnreadEntry {
+        path: id.clone(),
+        id,
+        count: u.count,
+        mention: u.noticeable,
+        since: u.since,
+    }));
+    entries
+}
+
+/// Unread entries of the `targets` channels.
+fn select(unread: Vec<UnreadEntry>, targets: &[ChannelEntry]) -> Vec<UnreadEntry> {
+    unread
+        .into_iter()
+        .filter(|u| targets.iter().any(|t| t.id == u.id))
+        .collect()
+}
+
+async fn ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let entries = get_unread_entries(conf, &mut tree).await?;
+    output::render(format, &entries)?;
+
+    let total: i32 = entries.iter().map(|e| e.count).sum();
+    let mentions = entries.iter().filter(|e| e.mention).count();
+    let summary = format!(
+        "{} unread messages in {} channels, {} with mentions",
+        total,
+        entries.len(),
+        mentions
+    );
+    // keep stdout parsable for structured formats
+    if format.is_text() {
+        println!("\n{}", summary);
+    } else {
+        eprintln!("{}", summary);
+    }
+
+    Ok(())
+}
+
+async fn read(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let mut tree = channel::get_channel_tree(conf).await?;
+    let unread = get_unread_entries(conf, &mut tree).await?;
+    let targets: Vec<UnreadEntry> = if matches.is_present("all") {
+        unread
+    } else {
+        let name = matches.value_of("channel_name").unwrap();
+        let targets = tree
+            .entries(Path::new(name), matches.is_present("recursive"))
+            .with_context(|| format!("channel name not found: {}", name))?;
+        select(unread, &targets)
+    };
+    if targets.is_empty() {
+        println!("no unread channels");
+        return Ok(());
+    }
+
+    let total = targets.len();
+    let pb = ProgressBar::new(total as u64);
+    let pb = &pb;
+    let mut failures: Vec<(String, String)> = stream::iter(targets)
+        .map(|u| async move {
+            let res = notification_api::read_channel(conf, &u.id).await;
+            pb.inc(1);
+            res.err().map(|e| (u.path, e.to_string()))
+        })
+        .buffer_unordered(CONCURRENCY)
+        .filter_map(future::ready)
+        .collect()
+        .await;
+    pb.finish_and_clear();
+
+    if failures.is_empty() {
+        println!("marked {} channels as read", total);
+        return Ok(());
+    }
+
+    failures.sort();
+    eprintln!("failed to read {} of {} channels:", failures.len(), total);
+    for (path, e) in failures.iter() {
+        eprintln!("  {}: {}", path, e);
+    }
+    bail!("{} channels were not marked as read", failures.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_traq::models::{Channel, ChannelList};
+
+    use super::*;
+
+    fn unread(id: &str, count: i32) -> UnreadChannel {
+        UnreadChannel::new(
+            id.to_owned(),
+            count,
+            false,
+            String::new(),
+            String::new(),
+            String::new(),
+        )
+    }
+
+    fn tree() -> ChannelTree {
+        let ch = |id: &str, name: &str, parent: Option<&str>, children: &[&str]| {
+            Channel::new(
+                id.to_owned(),
+                parent.map(str::to_owned),
+                false,
+                false,
+                String::new(),
+                name.to_owned(),
+                children.iter().map(|c| c.to_string()).collect(),
+            )
+        };
+        channel::build_channel_tree(ChannelList::new(vec![
+            ch("a", "team", None, &["b"]),
+            ch("b", "SysAd", Some("a"), &[]),
+            ch("c", "random", None, &[]),
+        ]))
+    }
+
+    fn ids(entries: &[UnreadEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut tree = tree();
+        let entries = merge(
+            tree.entries(Path::new("/"), true).unwrap(),
+            vec![unread("dm", 1), unread("c", 2), unread("b", 3)],
+        );
+        assert_eq!(ids(&entries), ["b", "c", "dm"]);
+        assert_eq!(entries[0].path, "/team/SysAd");
+        assert_eq!(entries[2].path, "dm");
+    }
+
+    #[test]
+    fn test_select() {
+        let mut tree = tree();
+        let all = merge(
+            tree.entries(Path::new("/"), true).unwrap(),
+            vec![unread("a", 1), unread("b", 1), unread("c", 1)],
+        );
+        let team = tree.entries(Path::new("/team"), false).unwrap();
+        assert_eq!(ids(&select(all, &team)), ["a"]);
+
+        let all = merge(
+            tree.entries(Path::new("/"), true).unwrap(),
+            vec![unread("a", 1), unread("b", 1), unread("c", 1)],
+        );
+        let team = tree.entries(Path::new("/team"), true).unwrap();
+        assert_eq!(ids(&select(all, &team)), ["a", "b"]);
+    }
+}
@@ -0,0 +1,290 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use rust_traq::{
+    apis::{configuration::Configuration, group_api, user_api},
+    models::{User, UserAccountState},
+};
+use serde::Serialize;
+
+use super::{
+    channel,
+    msg::format_time,
+    output::{self, OutputFormat, Render},
+};
+use crate::{cache, utils::fuzzy};
+
+const USERS_CACHE: &str = "users";
+/// Renamed users are looked up by their old names until the cache expires.
+const USERS_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Serialize)]
+pub struct UserEntry {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+    pub bot: bool,
+    pub state: &'static str,
+}
+
+impl From<User> for UserEntry {
+    fn from(u: User) -> Self {
+        Self {
+            id: u.id,
+            name: u.name,
+            display_name: u.display_name,
+            bot: u.bot,
+            state: state_name(u.state),
+        }
+    }
+}
+
+impl Render for UserEntry {
+    fn header() -> &'static [&'static str] {
+        &["id", "name", "display_name", "bot", "state"]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.display_name.clone(),
+            self.bot.to_string(),
+            self.state.to_owned(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        let mut text = format!("@{}  {}", self.name, self.display_name);
+        if self.bot {
+            text.push_str("  [bot]");
+        }
+        if self.state != "active" {
+            text.push_str(&format!("  ({})", self.state));
+        }
+        text
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserProfile {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+    pub bot: bool,
+    pub state: &'static str,
+    pub bio: String,
+    pub twitter_id: String,
+    pub groups: Vec<String>,
+    pub tags: Vec<String>,
+    pub last_online: Option<String>,
+    pub home_channel: Option<String>,
+}
+
+impl Render for UserProfile {
+    fn header() -> &'static [&'static str] {
+        &[
+            "id",
+            "name",
+            "display_name",
+            "bot",
+            "state",
+            "bio",
+            "twitter_id",
+            "groups",
+            "tags",
+            "last_online",
+            "home_channel",
+        ]
+    }
+
+    fn record(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            self.name.clone(),
+            self.display_name.clone(),
+            self.bot.to_string(),
+            self.state.to_owned(),
+            self.bio.clone(),
+            self.twitter_id.clone(),
+            self.groups.join(" "),
+            self.tags.join(" "),
+            self.last_online.clone().unwrap_or_default(),
+            self.home_channel.clone().unwrap_or_default(),
+        ]
+    }
+
+    fn text(&self) -> String {
+        let mut lines = vec![
+            format!("{} (@{})", self.display_name, self.name),
+            format!("id:           {}", self.id),
+            format!("state:        {}", self.state),
+        ];
+        if self.bot {
+            lines.push("bot:          true".to_owned());
+        }
+        if !self.twitter_id.is_empty() {
+            lines.push(format!("twitter:      @{}", self.twitter_id));
+        }
+        lines.push(format!(
+            "last online:  {}",
+            self.last_online
+                .as_deref()
+                .map_or_else(|| "-".to_owned(), format_time)
+        ));
+        lines.push(format!(
+            "home channel: {}",
+            self.home_channel.as_deref().unwrap_or("-")
+        ));
+        lines.push(format!("groups:       {}", self.groups.join(", ")));
+        lines.push(format!("tags:         {}", self.tags.join(", ")));
+        if !self.bio.is_empty() {
+            lines.push(String::new());
+            lines.push(self.bio.clone());
+        }
+        lines.join("\n")
+    }
+}
+
+pub fn state_name(state: UserAccountState) -> &'static str {
+    match state {
+        UserAccountState::deactivated => "deactivated",
+        UserAccountState::active => "active",
+        UserAccountState::suspended => "suspended",
+    }
+}
+
+pub async fn user(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    match matches.subcommand() {
+        ("ls", Some(m)) => ls(conf, m).await,
+        ("show", Some(m)) => show(conf, m).await,
+        ("find", Some(m)) => find(conf, m).await,
+        (x, _) => bail!("unknown user command '{}'", x),
+    }
+}
+
+/// Fetches all users including suspended ones and refreshes the cache.
+pub(crate) async fn get_users(conf: &Configuration) -> Result<Vec<User>> {
+    let users = user_api::get_users(conf, Some(true))
+        .await
+        .with_context(|| "cannot get users")?;
+    let _ = cache::store(USERS_CACHE, &users);
+    Ok(users)
+}
+
+/// Cached users, fetched if there is no cache yet or it is older than an hour.
+pub(crate) async fn get_cached_users(conf: &Configuration) -> Result<Vec<User>> {
+    match cache::load_fresh(USERS_CACHE, USERS_CACHE_TTL) {
+        Ok(users) => Ok(users),
+        Err(_) => get_users(conf).await,
+    }
+}
+
+/// Looks up a user by `name` or `@name`. The cache is refreshed once if it is not found.
+pub(crate) async fn resolve(conf: &Configuration, name: &str) -> Result<User> {
+    let name = name.trim_start_matches('@');
+    let users = get_cached_users(conf).await?;
+    if let Some(user) = users.into_iter().find(|u| u.name == name) {
+        return Ok(user);
+    }
+    get_users(conf)
+        .await?
+        .into_iter()
+        .find(|u| u.name == name)
+        .with_context(|| format!("user not found: @{}", name))
+}
+
+async fn ls(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let users = if matches.is_present("refresh") {
+        get_users(conf).await?
+    } else {
+        get_cached_users(conf).await?
+    };
+    let bots = matches.is_present("bots");
+    let active = matches.is_present("active");
+
+    let mut entries: Vec<UserEntry> = users
+        .into_iter()
+        .filter(|u| !bots || u.bot)
+        .filter(|u| !active || u.state == UserAccountState::active)
+        .map(UserEntry::from)
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    output::render(format, &entries)
+}
+
+async fn show(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let user = resolve(conf, matches.value_of("user").unwrap()).await?;
+    let detail = user_api::get_user(conf, &user.id)
+        .await
+        .with_context(|| format!("cannot get @{}", user.name))?;
+
+    let profile = UserProfile {
+        id: detail.id,
+        name: detail.name,
+        display_name: detail.display_name,
+        bot: detail.bot,
+        state: state_name(detail.state),
+        bio: detail.bio,
+        twitter_id: detail.twitter_id,
+        groups: group_names(conf, &detail.groups).await?,
+        tags: detail.tags.into_iter().map(|t| t.tag).collect(),
+        last_online: detail.last_online,
+        home_channel: channel_path(conf, detail.home_channel).await?,
+    };
+    output::render(format, &[profile])
+}
+
+/// Names of groups by their ids.
+pub(crate) async fn group_names(conf: &Configuration, ids: &[String]) -> Result<Vec<String>> {
+    let groups: BTreeMap<String, String> = group_api::get_user_groups(conf)
+        .await
+        .with_context(|| "cannot get groups")?
+        .into_iter()
+        .map(|g| (g.id, g.name))
+        .collect();
+    let names = ids
+        .iter()
+        .map(|id| groups.get(id).cloned().unwrap_or_else(|| id.clone()))
+        .collect();
+    Ok(names)
+}
+
+/// Path of a channel such as a home channel. The id is kept if it is not in the tree.
+pub(crate) async fn channel_path(
+    conf: &Configuration,
+    id: Option<String>,
+) -> Result<Option<String>> {
+    let id = match id {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+    let tree = channel::get_channel_tree(conf).await?;
+    Ok(Some(tree.paths().remove(&id).unwrap_or(id)))
+}
+
+async fn find(conf: &Configuration, matches: &ArgMatches<'_>) -> Result<()> {
+    let format = OutputFormat::from_matches(matches)?;
+    let query = matches.value_of("query").unwrap().trim_start_matches('@');
+    let limit: usize = matches
+        .value_of("limit")
+        .unwrap()
+        .parse()
+        .with_context(|| "limit must be a number")?;
+
+    let users = get_cached_users(conf).await?;
+    // match display names too, they often differ from the ids
+    let keys: Vec<String> = users
+        .iter()
+        .map(|u| format!("{} {}", u.name, u.display_name))
+        .collect();
+    let entries: Vec<UserEntry> = fuzzy::rank(query, keys.iter().map(String::as_str))
+        .into_iter()
+        .take(limit)
+        .map(|(i, _)| UserEntry::from(users[i].clone()))
+        .collect();
+    output::render(format, &entries)
+}
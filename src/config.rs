@@ -1,4 +1,5 @@
 use std::{
+    env,
     fs::{DirBuilder, File},
     io::prelude::*,
     path::PathBuf,
@@ -92,6 +93,29 @@ impl Config {
     }
 }
 
+pub fn dir_path() -> Result<PathBuf> {
+    let home = env::var("HOME").with_context(|| "cannot get home dir")?;
+
+    let mut path = PathBuf::new();
+    path.push(home);
+    path.push(".config");
+    path.push("qui");
+    Ok(path)
+}
+
+/// `$XDG_DATA_HOME/qui`, or `~/.local/share/qui` if it is not set.
+pub fn data_dir_path() -> Result<PathBuf> {
+    let mut path = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = env::var("HOME").with_context(|| "cannot get home dir")?;
+            [home.as_str(), ".local", "share"].iter().collect()
+        }
+    };
+    path.push("qui");
+    Ok(path)
+}
+
 fn save_file(path: PathBuf, content: &[u8]) -> Result<()> {
     let mut file = File::create(path.as_path()).with_context(|| "cannot create file")?;
     file.write_all(content)
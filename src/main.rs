@@ -1,12 +1,17 @@
 use anyhow::{bail, Context, Result};
 use clap::Shell;
 use qui::{
-    cli::{clap_app, handle},
+    cli::{
+        clap_app, complete,
+        config::ConfigEntry,
+        handle,
+        output::{self, OutputFormat},
+    },
     config::{self, Config},
     token::{self, TraqOAuthParam},
 };
 use rust_traq::apis::configuration::Configuration;
-use std::{env, io, path::PathBuf};
+use std::{io, path::PathBuf};
 // use termion::{input::MouseTerminal, raw::IntoRawMode, screen::AlternateScreen};
 // use tui::{backend::TermionBackend, Terminal};
 
@@ -25,8 +30,7 @@ async fn main() -> Result<()> {
     //     eprintln!("{}", e);
     // }
 
-    let mut app = clap_app::clap_app();
-    let matches = app.clone().get_matches();
+    let matches = clap_app::clap_app().get_matches();
 
     // completions
     if let Some(s) = matches.value_of("completions") {
@@ -38,10 +42,20 @@ async fn main() -> Result<()> {
             "elvish" => Shell::Elvish,
             _ => bail!("no completions avaible for '{}'", s),
         };
-        app.gen_completions_to(env!("CARGO_BIN_NAME"), shell, &mut io::stdout());
+        let mut script = Vec::new();
+        clap_app::completion_app().gen_completions_to(env!("CARGO_BIN_NAME"), shell, &mut script);
+        let script = String::from_utf8(script)?;
+        print!(
+            "{}",
+            complete::with_channel_completion(shell, env!("CARGO_BIN_NAME"), script)
+        );
         return Ok(());
     }
 
+    if let Some(m) = matches.subcommand_matches("__complete") {
+        return complete::complete(m);
+    }
+
     if matches.is_present("set-config") {
         let config = config::ui::ui(get_conf_path()?)?;
         config.save()?;
@@ -51,7 +65,10 @@ async fn main() -> Result<()> {
 
     if matches.is_present("show-config") {
         let config = Config::load(get_conf_path()?)?;
-        println!("{:?}", config.data);
+        output::render(
+            OutputFormat::from_matches(&matches)?,
+            &[ConfigEntry::from(&config.data)],
+        )?;
         return Ok(());
     }
 
@@ -132,11 +149,5 @@ fn get_token_path() -> Result<PathBuf> {
 }
 
 fn get_conf_path() -> Result<PathBuf> {
-    let home = env::var("HOME").with_context(|| "cannot get home dir")?;
-
-    let mut path = PathBuf::new();
-    path.push(home);
-    path.push(".config");
-    path.push("qui");
-    Ok(path)
+    config::dir_path()
 }
@@ -1,4 +1,6 @@
+pub mod editor;
 pub mod event;
+pub mod fuzzy;
 use std::{
     cmp::min,
     ops::{Add, Sub},
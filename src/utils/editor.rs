@@ -0,0 +1,70 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    process::Command,
+};
+
+use anyhow::{bail, Context, Result};
+use rand::{distributions::Alphanumeric, Rng};
+
+/// Opens `$EDITOR` (or `vi`) on a temporary file containing `initial` and returns what was saved.
+pub fn edit(initial: &str) -> Result<String> {
+    let editor = env::var("EDITOR").unwrap_or_else(|_| "vi".to_owned());
+    let (path, mut file) = create_temp_file()?;
+    let written = file.write_all(initial.as_bytes());
+    drop(file);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&path);
+        return Err(e).with_context(|| format!("cannot write {}", path.display()));
+    }
+
+    // `$EDITOR` may contain arguments like `code --wait`
+    let mut args = editor.split_whitespace();
+    let program = args.next().with_context(|| "$EDITOR is empty");
+    let status = program.and_then(|program| {
+        Command::new(program)
+            .args(args)
+            .arg(&path)
+            .status()
+            .with_context(|| format!("cannot start {}", editor))
+    });
+
+    let content = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    if !status?.success() {
+        bail!("{} exited with an error", editor);
+    }
+    Ok(content?)
+}
+
+/// Creates a new file with a random name, readable only by the user on unix.
+/// `create_new` fails instead of following a file or symlink planted at the path.
+fn create_temp_file() -> Result<(PathBuf, File)> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    for _ in 0..16 {
+        let name: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(16)
+            .map(char::from)
+            .collect();
+        let path = env::temp_dir().join(format!("qui-{}.md", name));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).with_context(|| format!("cannot create {}", path.display())),
+        }
+    }
+    bail!(
+        "cannot create a temporary file in {}",
+        env::temp_dir().display()
+    )
+}
@@ -0,0 +1,73 @@
+/// Scores `target` against `query` as a case-insensitive subsequence match.
+/// Returns `None` when `target` does not contain every character of `query`.
+pub fn score(query: &str, target: &str) -> Option<i64> {
+    let target: Vec<char> = target.chars().collect();
+    let last_component = target.iter().rposition(|&c| c == '/').map_or(0, |i| i + 1);
+
+    let mut score = 0;
+    let mut next = 0;
+    let mut prev: Option<usize> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let pos = (next..target.len()).find(|&i| eq_ignore_case(target[i], q))?;
+
+        score += 1;
+        if pos == 0 || matches!(target[pos - 1], '/' | '_' | '-' | ' ' | '.') {
+            score += 8;
+        }
+        match prev {
+            Some(p) if p + 1 == pos => score += 5,
+            Some(p) => score -= ((pos - p - 1) as i64).min(3),
+            None if pos >= last_component => score += 10,
+            None => {}
+        }
+
+        prev = Some(pos);
+        next = pos + 1;
+    }
+
+    // prefer shorter targets among equally good matches
+    Some(score * 10 - target.len() as i64 / 4)
+}
+
+/// Returns indices of matched `targets` with their scores, best first.
+pub fn rank<'a>(query: &str, targets: impl Iterator<Item = &'a str>) -> Vec<(usize, i64)> {
+    let mut ranked: Vec<(usize, i64)> = targets
+        .enumerate()
+        .filter_map(|(i, t)| score(query, t).map(|s| (i, s)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        assert!(score("abc", "/a/b/c").is_some());
+        assert!(score("ABC", "/a/b/c").is_some());
+        assert!(score("abd", "/a/b/c").is_none());
+        assert_eq!(score("", "/a"), Some(0));
+    }
+
+    #[test]
+    fn test_rank() {
+        let targets = [
+            "/general",
+            "/team/SysAd/bot",
+            "/team/SysAd",
+            "/random/sysad-memo",
+        ];
+        let ranked = rank("sysad", targets.iter().copied());
+        let order: Vec<&str> = ranked.iter().map(|(i, _)| targets[*i]).collect();
+        assert_eq!(
+            order,
+            vec!["/team/SysAd", "/random/sysad-memo", "/team/SysAd/bot"]
+        );
+    }
+}